@@ -0,0 +1,49 @@
+//! Parsing time of a declaration with the engine state shared across the macro invocations, compared with the former
+//! path building a new engine state for every invocation.
+use std::time::{Duration, Instant};
+
+use nu_signature_core::parse;
+
+const SIGNATURE: &str = r#"
+# description hello world
+extern hello_world [
+    pos_arg: int                # pos_arg description
+    opt_arg?: string = "hello"  # opt_arg description
+    ...rest: list<record<a: int, b: string>>
+    --switch(-s)                # switch description
+    --flag: string              # flag description
+]: [nothing -> string, string -> record<a: int>];
+"#;
+
+const ITERATIONS: u32 = 200;
+
+fn time(parse: impl Fn() -> Result<(String, nu_protocol::Signature), String>) -> Duration {
+    let start = Instant::now();
+    let res = parse();
+    let elapsed = start.elapsed();
+    if let Err(e) = res {
+        panic!("failed to parse the declaration: {}", e);
+    }
+    elapsed
+}
+
+fn bench(name: &str, parse: impl Fn() -> Result<(String, nu_protocol::Signature), String>) {
+    let first = time(&parse);
+    let total = (0..ITERATIONS).map(|_| time(&parse)).sum::<Duration>();
+    println!("{}: first invocation {:?}, next {} invocations {:?} per invocation", name, first, ITERATIONS, total / ITERATIONS);
+}
+
+fn main() {
+    bench("new engine state per invocation", || {
+        parse::extract_declaration_in(&parse::new_engine(), SIGNATURE.as_bytes())
+    });
+    bench("shared engine state", || parse::extract_declaration(SIGNATURE.as_bytes()));
+
+    let item = proc_macro2::TokenTree::Literal(proc_macro2::Literal::string(SIGNATURE));
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let res = nu_signature_core::make_signature(item.clone().into());
+        assert!(!res.to_string().contains("compile_error"), "failed to make signature: {}", res);
+    }
+    println!("make_signature with the shared engine state: {:?} per invocation", start.elapsed() / ITERATIONS);
+}
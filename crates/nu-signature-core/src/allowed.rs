@@ -0,0 +1,134 @@
+//! Fixed sets of values accepted by a string parameter, written where its completer would be:
+//! `extern convert [--format: string@[json yaml toml]]`.
+//!
+//! The lists are removed before parsing, so the parameter is a plain `string` in the signature, without completion.
+//! The plugin commands get an enum for the parameter instead of a `String`.
+use nu_protocol::Flag;
+
+/// The values of each parameter, keyed by the name of a positional parameter, or by the flag with its dashes
+/// (`--format`, or `-f` without long name), so that a positional and a flag with the same name don't mix up.
+#[derive(Default)]
+pub struct AllowedValues(Vec<(String, Vec<String>)>);
+
+impl AllowedValues {
+    /// Values accepted by the positional or rest parameter `name`.
+    pub fn positional(&self, name: &str) -> Option<&[String]> {
+        self.get(name)
+    }
+
+    /// Values accepted by the flag.
+    pub fn flag(&self, flag: &Flag) -> Option<&[String]> {
+        match flag.short {
+            Some(short) if flag.long.is_empty() => self.get(&format!("-{}", short)),
+            _ => self.get(&format!("--{}", flag.long)),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&[String]> {
+        self.0.iter().find(|(name, _)| name == key).map(|(_, values)| values.as_slice())
+    }
+
+    /// Removes the lists of values from the declaration.
+    pub(crate) fn extract(source: &str) -> Result<(Self, String), String> {
+        let mut allowed = Self::default();
+        let mut res = String::with_capacity(source.len());
+        let mut chars = source.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '#' => {
+                    let end = source[i..].find('\n').map_or(source.len(), |e| i + e);
+                    res.push_str(&source[i..end]);
+                    while chars.next_if(|(j, _)| *j < end).is_some() {}
+                }
+                '"' | '\'' | '`' => {
+                    let end = string_end(source, i).ok_or("unterminated string")?;
+                    res.push_str(&source[i..end]);
+                    while chars.next_if(|(j, _)| *j < end).is_some() {}
+                }
+                '@' if source[i + 1..].starts_with('[') => {
+                    let (values, end) = values(source, i + 2)?;
+                    let param = param_key(&source[..i])?;
+                    if allowed.get(&param).is_some() {
+                        return Err(format!("allowed values given twice for `{}`", param));
+                    }
+                    allowed.0.push((param, values));
+                    while chars.next_if(|(j, _)| *j < end).is_some() {}
+                }
+                c => res.push(c),
+            }
+        }
+        Ok((allowed, res))
+    }
+}
+
+/// End of the string starting at `start`, after its closing quote.
+fn string_end(source: &str, start: usize) -> Option<usize> {
+    let quote = source[start..].chars().next()?;
+    let mut escaped = false;
+    for (i, c) in source[start + 1..].char_indices() {
+        match c {
+            '\\' if quote == '"' && !escaped => escaped = true,
+            c if c == quote && !escaped => return Some(start + 1 + i + 1),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+/// Parses the values of the list starting at `start`, after the `[`, up to the end of the list.
+fn values(source: &str, start: usize) -> Result<(Vec<String>, usize), String> {
+    let mut values = Vec::new();
+    let mut pos = start;
+    loop {
+        let rest = &source[pos..];
+        let Some(c) = rest.chars().next() else {
+            return Err("unterminated list of allowed values".to_string());
+        };
+        match c {
+            ']' => break,
+            c if c.is_whitespace() || c == ',' => pos += c.len_utf8(),
+            '"' | '\'' | '`' => {
+                let end = string_end(source, pos).ok_or("unterminated string in the allowed values")?;
+                let value = &source[pos + 1..end - 1];
+                values.push(if c == '"' { value.replace("\\\"", "\"").replace("\\\\", "\\") } else { value.to_string() });
+                pos = end;
+            }
+            _ => {
+                let len = rest.find(|c: char| c.is_whitespace() || c == ',' || c == ']').unwrap_or(rest.len());
+                values.push(rest[..len].to_string());
+                pos += len;
+            }
+        }
+    }
+    if values.is_empty() {
+        return Err("the list of allowed values is empty".to_string());
+    }
+    if let Some(value) = values.iter().enumerate().find_map(|(i, value)| values[..i].contains(value).then_some(value)) {
+        return Err(format!("the allowed value `{}` is given twice", value));
+    }
+    Ok((values, pos + 1))
+}
+
+/// Key of the parameter whose type ends `text`: `--format(-f): string` is `--format`, `format?: string` is `format`.
+fn param_key(text: &str) -> Result<String, String> {
+    let before_type = text
+        .strip_suffix("string")
+        .ok_or("allowed values only apply to `string` parameters")?
+        .trim_end()
+        .strip_suffix(':')
+        .ok_or("allowed values only apply to parameters")?
+        .trim_end();
+    let before_type = match before_type.strip_suffix(')') {
+        Some(flag) => flag.rsplit_once('(').map_or(flag, |(long, _)| long),
+        None => before_type,
+    };
+    let before_type = before_type.trim_end_matches('?');
+    let start = before_type
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+        .map_or(0, |i| i + 1);
+    let key = &before_type[start..];
+    if key.trim_start_matches('-').is_empty() {
+        return Err("allowed values only apply to parameters".to_string());
+    }
+    Ok(key.to_string())
+}
@@ -0,0 +1,235 @@
+//! Attributes written before the declaration, like `@category`, `@search-terms` and `@example`.
+//!
+//! The nu parser doesn't know them, so they are parsed and removed from the source before the declaration is.
+use std::ops::Range;
+
+use nu_protocol::{Category, Signature, Value};
+
+#[derive(Debug, Default)]
+pub struct Attributes {
+    pub category: Option<Category>,
+    pub search_terms: Vec<String>,
+    pub examples: Vec<Example>,
+}
+
+#[derive(Debug)]
+pub struct Example {
+    pub example: String,
+    pub description: String,
+    pub result: Option<Value>,
+}
+
+/// Error located in the source, so it can be reported on the right part of the literal.
+#[derive(Debug)]
+pub struct AttributeError {
+    pub message: String,
+    pub range: Range<usize>,
+}
+
+impl Attributes {
+    pub fn apply(&self, sig: &mut Signature) {
+        sig.category = self.category.clone().unwrap_or(Category::Experimental);
+        sig.search_terms.clone_from(&self.search_terms);
+    }
+}
+
+struct Token<'a> {
+    text: &'a str,
+    start: usize,
+}
+
+impl Token<'_> {
+    fn range(&self) -> Range<usize> {
+        self.start..self.start + self.text.len()
+    }
+}
+
+/// Splits the attributes from the rest of the source. The returned source has the attribute lines removed.
+pub fn extract_attributes(source: &str) -> Result<(Attributes, String), AttributeError> {
+    let mut attributes = Attributes::default();
+    let mut declaration = String::with_capacity(source.len());
+    let mut pos = 0;
+    while pos < source.len() {
+        let line_end = source[pos..].find('\n').map_or(source.len(), |i| pos + i + 1);
+        let line = &source[pos..line_end];
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('@') {
+            pos = parse_attribute(source, line_end - trimmed.len(), &mut attributes)?;
+        } else if trimmed.is_empty() || trimmed.starts_with('#') {
+            declaration.push_str(line);
+            pos = line_end;
+        } else {
+            declaration.push_str(&source[pos..]);
+            break;
+        }
+    }
+    Ok((attributes, declaration))
+}
+
+/// Parses the attribute starting at `start` (on the `@`), returns the position after it.
+fn parse_attribute(source: &str, start: usize, attributes: &mut Attributes) -> Result<usize, AttributeError> {
+    let name_end = source[start + 1..]
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .map_or(source.len(), |i| start + 1 + i);
+    let name = &source[start + 1..name_end];
+    let (args, end) = tokenize(source, name_end)?;
+    let error = |message: String, range: Range<usize>| AttributeError { message, range };
+    let name_range = start..name_end;
+
+    match name {
+        "category" => {
+            let [category] = args.as_slice() else {
+                return Err(error("`@category` expects exactly one category name".to_string(), name_range));
+            };
+            attributes.category = Some(category_from_str(&string_arg(category)?));
+        }
+        "search-terms" => {
+            if args.is_empty() {
+                return Err(error("`@search-terms` expects at least one search term".to_string(), name_range));
+            }
+            for arg in &args {
+                attributes.search_terms.push(string_arg(arg)?);
+            }
+        }
+        "example" => {
+            let mut positionals = Vec::new();
+            let mut result = None;
+            let mut iter = args.iter();
+            while let Some(arg) = iter.next() {
+                match arg.text {
+                    "--result" => {
+                        let value = iter.next()
+                            .ok_or_else(|| error("`--result` expects a value".to_string(), arg.range()))?;
+                        let parsed = crate::parse_value(value.text.as_bytes())
+                            .map_err(|e| error(format!("invalid example result: {}", e), value.range()))?;
+                        result = Some(parsed);
+                    }
+                    flag if flag.starts_with("--") => {
+                        return Err(error(format!("unknown flag `{}` for `@example`", flag), arg.range()));
+                    }
+                    _ => positionals.push(arg),
+                }
+            }
+            let [description, example] = positionals.as_slice() else {
+                return Err(error("`@example` expects a description and an example: `@example \"description\" { code }`".to_string(), name_range));
+            };
+            let example = match example.text.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                Some(code) => code.trim().to_string(),
+                None => string_arg(example)?,
+            };
+            attributes.examples.push(Example {
+                example,
+                description: string_arg(description)?,
+                result,
+            });
+        }
+        _ => {
+            return Err(error(
+                format!("unknown attribute `@{}`, expected `@category`, `@search-terms` or `@example`", name),
+                name_range,
+            ))
+        }
+    }
+    Ok(end)
+}
+
+/// Splits the arguments of an attribute, up to the end of the line. Brackets and strings can span several lines.
+fn tokenize(source: &str, mut pos: usize) -> Result<(Vec<Token<'_>>, usize), AttributeError> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    loop {
+        while pos < bytes.len() && matches!(bytes[pos], b' ' | b'\t' | b'\r') {
+            pos += 1;
+        }
+        match bytes.get(pos) {
+            None => return Ok((tokens, pos)),
+            Some(b'\n') => return Ok((tokens, pos + 1)),
+            Some(b'#') => {
+                pos = source[pos..].find('\n').map_or(source.len(), |i| pos + i);
+                continue;
+            }
+            _ => {}
+        }
+        let start = pos;
+        let mut depth = 0usize;
+        while pos < bytes.len() {
+            match bytes[pos] {
+                quote @ (b'"' | b'\'' | b'`') => {
+                    pos += 1;
+                    while pos < bytes.len() && bytes[pos] != quote {
+                        if quote == b'"' && bytes[pos] == b'\\' {
+                            pos += 1;
+                        }
+                        pos += 1;
+                    }
+                    if pos >= bytes.len() {
+                        return Err(AttributeError { message: "unterminated string".to_string(), range: start..pos });
+                    }
+                }
+                b'[' | b'{' | b'(' => depth += 1,
+                b']' | b'}' | b')' => {
+                    depth = depth.checked_sub(1).ok_or_else(|| AttributeError {
+                        message: "unbalanced closing bracket".to_string(),
+                        range: pos..pos + 1,
+                    })?
+                }
+                b' ' | b'\t' | b'\r' | b'\n' if depth == 0 => break,
+                _ => {}
+            }
+            pos += 1;
+        }
+        if depth > 0 {
+            return Err(AttributeError { message: "unclosed bracket".to_string(), range: start..pos });
+        }
+        tokens.push(Token { text: &source[start..pos], start });
+    }
+}
+
+fn string_arg(token: &Token) -> Result<String, AttributeError> {
+    if !token.text.starts_with(['"', '\'', '`']) {
+        return Ok(token.text.to_string());
+    }
+    match crate::parse_value(token.text.as_bytes()) {
+        Ok(Value::String { val, .. }) => Ok(val),
+        Ok(value) => Err(AttributeError {
+            message: format!("string expected, found {}", value.get_type()),
+            range: token.range(),
+        }),
+        Err(e) => Err(AttributeError { message: e, range: token.range() }),
+    }
+}
+
+fn category_from_str(category: &str) -> Category {
+    match category.to_lowercase().as_str() {
+        "bits" => Category::Bits,
+        "bytes" => Category::Bytes,
+        "chart" => Category::Chart,
+        "conversions" => Category::Conversions,
+        "core" => Category::Core,
+        "database" => Category::Database,
+        "date" => Category::Date,
+        "debug" => Category::Debug,
+        "default" => Category::Default,
+        "removed" => Category::Removed,
+        "env" => Category::Env,
+        "experimental" => Category::Experimental,
+        "filesystem" => Category::FileSystem,
+        "filters" => Category::Filters,
+        "formats" => Category::Formats,
+        "generators" => Category::Generators,
+        "hash" => Category::Hash,
+        "history" => Category::History,
+        "math" => Category::Math,
+        "misc" => Category::Misc,
+        "network" => Category::Network,
+        "path" => Category::Path,
+        "platform" => Category::Platform,
+        "plugin" => Category::Plugin,
+        "random" => Category::Random,
+        "shells" => Category::Shells,
+        "strings" => Category::Strings,
+        "system" => Category::System,
+        "viewers" => Category::Viewers,
+        _ => Category::Custom(category.to_string()),
+    }
+}
@@ -1,7 +1,11 @@
-use chrono::{Datelike, Timelike};
 use nu_protocol::{Flag, PositionalArg, Signature};
 use proc_macro2::Literal;
 use quote::quote;
+
+use crate::attributes::Example;
+use crate::command;
+use crate::interpolate::Interpolations;
+
 fn shape_to_token(shape: &nu_protocol::SyntaxShape) -> proc_macro2::TokenStream {
     use nu_protocol::SyntaxShape;
     match shape {
@@ -80,7 +84,7 @@ fn shape_to_token(shape: &nu_protocol::SyntaxShape) -> proc_macro2::TokenStream
         SyntaxShape::VarWithOptType => quote! { nu_protocol::SyntaxShape::VarWithOptType },
     }
 }
-fn type_to_token(type_: &nu_protocol::Type) -> proc_macro2::TokenStream {
+pub fn type_to_token(type_: &nu_protocol::Type) -> proc_macro2::TokenStream {
     match type_ {
         nu_protocol::Type::Any => quote! { nu_protocol::Type::Any },
         nu_protocol::Type::Binary => quote! { nu_protocol::Type::Binary },
@@ -88,7 +92,7 @@ fn type_to_token(type_: &nu_protocol::Type) -> proc_macro2::TokenStream {
         nu_protocol::Type::Bool => quote! { nu_protocol::Type::Bool },
         nu_protocol::Type::CellPath => quote! { nu_protocol::Type::CellPath },
         nu_protocol::Type::Closure => quote! { nu_protocol::Type::Closure },
-        nu_protocol::Type::Custom(custom) => quote! { nu_protocol::Type::Custom(#custom) },
+        nu_protocol::Type::Custom(custom) => quote! { nu_protocol::Type::Custom(#custom.into()) },
         nu_protocol::Type::Date => quote! { nu_protocol::Type::Date },
         nu_protocol::Type::Duration => quote! { nu_protocol::Type::Duration },
         nu_protocol::Type::Error => quote! { nu_protocol::Type::Error },
@@ -110,7 +114,7 @@ fn type_to_token(type_: &nu_protocol::Type) -> proc_macro2::TokenStream {
                 .map(|(name, ty)| {
                     let name = Literal::string(name);
                     let ty = type_to_token(ty);
-                    quote! { (#name, #ty) }
+                    quote! { (String::from(#name), #ty) }
                 });
             let ty = match type_ {
                 nu_protocol::Type::Record(_) => quote! { Record },
@@ -125,93 +129,140 @@ fn type_to_token(type_: &nu_protocol::Type) -> proc_macro2::TokenStream {
     }
 }
 
+fn category_to_token(category: &nu_protocol::Category) -> proc_macro2::TokenStream {
+    use nu_protocol::Category;
+    match category {
+        Category::Bits => quote! { nu_protocol::Category::Bits },
+        Category::Bytes => quote! { nu_protocol::Category::Bytes },
+        Category::Chart => quote! { nu_protocol::Category::Chart },
+        Category::Conversions => quote! { nu_protocol::Category::Conversions },
+        Category::Core => quote! { nu_protocol::Category::Core },
+        Category::Custom(name) => quote! { nu_protocol::Category::Custom(String::from(#name)) },
+        Category::Database => quote! { nu_protocol::Category::Database },
+        Category::Date => quote! { nu_protocol::Category::Date },
+        Category::Debug => quote! { nu_protocol::Category::Debug },
+        Category::Default => quote! { nu_protocol::Category::Default },
+        Category::Removed => quote! { nu_protocol::Category::Removed },
+        Category::Env => quote! { nu_protocol::Category::Env },
+        Category::Experimental => quote! { nu_protocol::Category::Experimental },
+        Category::FileSystem => quote! { nu_protocol::Category::FileSystem },
+        Category::Filters => quote! { nu_protocol::Category::Filters },
+        Category::Formats => quote! { nu_protocol::Category::Formats },
+        Category::Generators => quote! { nu_protocol::Category::Generators },
+        Category::Hash => quote! { nu_protocol::Category::Hash },
+        Category::History => quote! { nu_protocol::Category::History },
+        Category::Math => quote! { nu_protocol::Category::Math },
+        Category::Misc => quote! { nu_protocol::Category::Misc },
+        Category::Network => quote! { nu_protocol::Category::Network },
+        Category::Path => quote! { nu_protocol::Category::Path },
+        Category::Platform => quote! { nu_protocol::Category::Platform },
+        Category::Plugin => quote! { nu_protocol::Category::Plugin },
+        Category::Random => quote! { nu_protocol::Category::Random },
+        Category::Shells => quote! { nu_protocol::Category::Shells },
+        Category::Strings => quote! { nu_protocol::Category::Strings },
+        Category::System => quote! { nu_protocol::Category::System },
+        Category::Viewers => quote! { nu_protocol::Category::Viewers },
+    }
+}
+
+/// `quote!` only writes finite floats, the infinities and NaN are written as the constants of `f64`.
+fn float_to_token(val: f64) -> proc_macro2::TokenStream {
+    if val.is_nan() {
+        quote! { f64::NAN }
+    } else if val == f64::INFINITY {
+        quote! { f64::INFINITY }
+    } else if val == f64::NEG_INFINITY {
+        quote! { f64::NEG_INFINITY }
+    } else {
+        quote! { #val }
+    }
+}
+
 pub fn value_to_token(value: &nu_protocol::Value) -> proc_macro2::TokenStream {
     use nu_protocol::{Value, Range};
     use std::ops::Bound;
     
     match value {
-        Value::Bool { val, .. } => quote! { nu_protocol::Value::bool(#val, Span::unknown()) },
-        Value::Int { val, .. } => quote! { nu_protocol::Value::int(#val, Span::unknown()) },
-        Value::Float { val, .. } => quote! { nu_protocol::Value::float(#val, Span::unknown()) },
-        Value::Filesize { val, .. } => quote! { nu_protocol::Value::filesize(#val, Span::unknown()) },
-        Value::Duration { val, .. } => quote! { nu_protocol::Value::duration(#val, Span::unknown()) },
+        Value::Bool { val, .. } => quote! { nu_protocol::Value::bool(#val, nu_protocol::Span::unknown()) },
+        Value::Int { val, .. } => quote! { nu_protocol::Value::int(#val, nu_protocol::Span::unknown()) },
+        Value::Float { val, .. } => {
+            let val = float_to_token(*val);
+            quote! { nu_protocol::Value::float(#val, nu_protocol::Span::unknown()) }
+        },
+        Value::Filesize { val, .. } => quote! { nu_protocol::Value::filesize(#val, nu_protocol::Span::unknown()) },
+        Value::Duration { val, .. } => quote! { nu_protocol::Value::duration(#val, nu_protocol::Span::unknown()) },
         Value::Date { val, .. } => {
-            let year = val.year();
-            let month = val.month();
-            let day = val.day();
-            let hour = val.hour();
-            let minute = val.minute();
-            let second = val.second();
-            let nanosecond = val.nanosecond();
-            let offset = val.offset().local_minus_utc();
-            quote! { 
-                unsafe {
-                    chrono::NaiveDate::from_ymd_opt(#year, #month, #day).unwrap_unchecked()
-                    .and_hms_nano_opt(#hour, #minute, #second, #nanosecond).unwrap_unchecked()
-                    .and_local_timezone(FixedOffset::east_opt(#offset).unwrap_unchecked())
-                    .unwrap()
-                }
-             }
+            // parsed back at runtime, so the generated code doesn't need chrono in the user's dependencies
+            let date = val.to_rfc3339();
+            quote! { nu_protocol::Value::date(#date.parse().unwrap(), nu_protocol::Span::unknown()) }
         },
         Value::Range { val, .. } => {
-            match **val {
+            let (range, start, next, end, inclusion) = match **val {
                 Range::IntRange(int_range) => {
-                    let start = {
-                        let s = int_range.start();
-                        quote!{ nu_protocol::Value::int(#s) }
-                    };
-                    let step = {
-                        let s = int_range.step();
-                        quote!{ nu_protocol::Value::int(#s) }
+                    let start = int_range.start();
+                    let next = start + int_range.step();
+                    let (end, inclusion) = match int_range.end() {
+                        Bound::Excluded(e) => (quote!{ nu_protocol::Value::int(#e, nu_protocol::Span::unknown()) }, quote!{ RightExclusive }),
+                        Bound::Included(e) => (quote!{ nu_protocol::Value::int(#e, nu_protocol::Span::unknown()) }, quote!{ Inclusive }),
+                        Bound::Unbounded => (quote!{ nu_protocol::Value::nothing(nu_protocol::Span::unknown()) }, quote!{ Inclusive }),
                     };
-                    let (end, range_inclusion) = match int_range.end() {
-                        Bound::Excluded(e) => (quote!{ nu_protocol::Value::int(#e) }, quote!{ RangeInclusion::RightExclusive }),
-                        Bound::Included(e) => (quote!{ nu_protocol::Value::int(#e) }, quote!{ RangeInclusion::Inclusive }),
-                        Bound::Unbounded => (quote!{ nu_protocol::Value::nothing() }, quote!{ RangeInclusion::Inclusive }),
-                    };
-                    quote! { nu_protocol::Value::Range { val: Box::new(nu_protocol::Range::IntRange(IntRange::new(#start, #step, #end, #range_inclusion, Span::unknown()))), span: Span::unknown() } }
+                    (
+                        quote! { IntRange },
+                        quote!{ nu_protocol::Value::int(#start, nu_protocol::Span::unknown()) },
+                        quote!{ nu_protocol::Value::int(#next, nu_protocol::Span::unknown()) },
+                        end,
+                        inclusion,
+                    )
                 }
                 Range::FloatRange(float_range) => {
-                    let start = {
-                        let s = float_range.start();
-                        quote!{ nu_protocol::Value::float(#s) }
-                    };
-                    let step = {
-                        let s = float_range.step();
-                        quote!{ nu_protocol::Value::float(#s) }
-                    };
-                    let (end, range_inclusion) = match float_range.end() {
-                        Bound::Excluded(e) => (quote!{ nu_protocol::Value::float(#e) }, quote!{ RangeInclusion::RightExclusive }),
-                        Bound::Included(e) => (quote!{ nu_protocol::Value::float(#e) }, quote!{ RangeInclusion::Inclusive }),
-                        Bound::Unbounded => (quote!{ nu_protocol::Value::nothing() }, quote!{ RangeInclusion::Inclusive }),
+                    let (start, next) = (float_range.start(), float_range.start() + float_range.step());
+                    let (start, next) = (float_to_token(start), float_to_token(next));
+                    let (end, inclusion) = match float_range.end().map(float_to_token) {
+                        Bound::Excluded(e) => (quote!{ nu_protocol::Value::float(#e, nu_protocol::Span::unknown()) }, quote!{ RightExclusive }),
+                        Bound::Included(e) => (quote!{ nu_protocol::Value::float(#e, nu_protocol::Span::unknown()) }, quote!{ Inclusive }),
+                        Bound::Unbounded => (quote!{ nu_protocol::Value::nothing(nu_protocol::Span::unknown()) }, quote!{ Inclusive }),
                     };
-                    quote! { nu_protocol::Value::Range { val: Box::new(nu_protocol::Range::FloatRange(FloatRange::new(#start, #step, #end, #range_inclusion, Span::unknown()))), span: Span::unknown() } }
+                    (
+                        quote! { FloatRange },
+                        quote!{ nu_protocol::Value::float(#start, nu_protocol::Span::unknown()) },
+                        quote!{ nu_protocol::Value::float(#next, nu_protocol::Span::unknown()) },
+                        end,
+                        inclusion,
+                    )
                 }
+            };
+            quote! {
+                nu_protocol::Value::range(
+                    nu_protocol::Range::#range(nu_protocol::#range::new(#start, #next, #end, nu_protocol::ast::RangeInclusion::#inclusion, nu_protocol::Span::unknown()).unwrap()),
+                    nu_protocol::Span::unknown(),
+                )
             }
         },
-        Value::String { val, .. } => quote! { nu_protocol::Value::String { val: String::from(#val), span: Span::unknown() } },
-        Value::Glob { val, .. } => quote! { nu_protocol::Value::Glob { val: String::from(#val), span: Span::unknown() } },
+        Value::String { val, .. } => quote! { nu_protocol::Value::string(#val, nu_protocol::Span::unknown()) },
+        Value::Glob { val, no_expand, .. } => quote! { nu_protocol::Value::glob(#val, #no_expand, nu_protocol::Span::unknown()) },
         Value::Record { val, .. } => {
-            let keys = val.columns().map(|k| quote! { String::from(#k) });
-            let values = val.values().map(value_to_token);
-            quote! { nu_protocol::Value::record(Record::from_raw_cols_vals(vec![#(#keys),*], vec![#(#values),*], Span::unknown(), Span::unknown())) }
+            let entries = val.iter().map(|(k, v)| {
+                let v = value_to_token(v);
+                quote! { (String::from(#k), #v) }
+            });
+            quote! { nu_protocol::Value::record(nu_protocol::Record::from_iter([#(#entries),*]), nu_protocol::Span::unknown()) }
         }
         Value::List { vals, .. } => {
             let values = vals.iter().map(value_to_token);
-            quote! { nu_protocol::Value::list(vec![#(#values),*], Span::unknown()) }
+            quote! { nu_protocol::Value::list(vec![#(#values),*], nu_protocol::Span::unknown()) }
         }
         Value::Closure { .. } => quote!{ compile_error!("Closure not supported") },
-        Value::Nothing { .. } => quote! { nu_protocol::Value::Nothing { span: Span::unknown() } },
+        Value::Nothing { .. } => quote! { nu_protocol::Value::nothing(nu_protocol::Span::unknown()) },
         Value::Error { .. } => quote!{ compile_error!("Error not supported") },
-        Value::Binary { val, .. } => quote! { nu_protocol::Value::binary(vec![#(#val),*], Span::unknown()) },
+        Value::Binary { val, .. } => quote! { nu_protocol::Value::binary(vec![#(#val),*], nu_protocol::Span::unknown()) },
         Value::CellPath { val, .. } => {
             let members = val.members
                 .iter()
                 .map(|x| match x {
-                    nu_protocol::ast::PathMember::String { val, optional, .. } => quote! { nu_protocol::ast::PathMember::string(#val, #optional, Span::unknown()) },
-                    nu_protocol::ast::PathMember::Int { val, optional, .. } => quote! { nu_protocol::ast::PathMember::int(#val, #optional, Span::unknown()) },
+                    nu_protocol::ast::PathMember::String { val, optional, .. } => quote! { nu_protocol::ast::PathMember::string(String::from(#val), #optional, nu_protocol::Span::unknown()) },
+                    nu_protocol::ast::PathMember::Int { val, optional, .. } => quote! { nu_protocol::ast::PathMember::int(#val, #optional, nu_protocol::Span::unknown()) },
                 });
-            quote! { nu_protocol::Value::CellPath { val: CellPath::new(vec![#(#members),*]), span: Span::unknown() } }
+            quote! { nu_protocol::Value::cell_path(nu_protocol::ast::CellPath { members: vec![#(#members),*] }, nu_protocol::Span::unknown()) }
         },
         Value::Custom { .. } => quote!{ compile_error!("Error not supported") },
         
@@ -235,13 +286,17 @@ where
         )
 }
 
-fn flag_to_token(flag: &Flag) -> proc_macro2::TokenStream {
+pub fn default_value_to_token(value: &nu_protocol::Value, interpolations: &Interpolations) -> proc_macro2::TokenStream {
+    interpolations.value_to_token(value).unwrap_or_else(|| value_to_token(value))
+}
+
+fn flag_to_token(flag: &Flag, interpolations: &Interpolations) -> proc_macro2::TokenStream {
     let long = &flag.long;
     let arg = option_to_token(&flag.arg, shape_to_token);
-    let desc = &flag.desc;
+    let desc = interpolations.string_to_token(&flag.desc);
     let short = option_to_token(&flag.short, |short| quote! { #short });
     let req = flag.required;
-    let def_value = option_to_token(&flag.default_value, value_to_token);
+    let def_value = option_to_token(&flag.default_value, |value| default_value_to_token(value, interpolations));
     quote! { nu_protocol::Flag {
         long: String::from(#long),
         arg: #arg,
@@ -252,11 +307,11 @@ fn flag_to_token(flag: &Flag) -> proc_macro2::TokenStream {
         var_id: None,
     }}
 }
-fn positional_arg_to_token(arg: &PositionalArg) -> proc_macro2::TokenStream {
+fn positional_arg_to_token(arg: &PositionalArg, interpolations: &Interpolations) -> proc_macro2::TokenStream {
     let name = &arg.name;
     let shape = shape_to_token(&arg.shape);
-    let desc = &arg.desc;
-    let default_value = option_to_token(&arg.default_value, value_to_token);
+    let desc = interpolations.string_to_token(&arg.desc);
+    let default_value = option_to_token(&arg.default_value, |value| default_value_to_token(value, interpolations));
     quote! { nu_protocol::PositionalArg {
         name: String::from(#name),
         shape: #shape,
@@ -266,17 +321,34 @@ fn positional_arg_to_token(arg: &PositionalArg) -> proc_macro2::TokenStream {
     } }
 }
 
-pub fn build_signature(name: &str, sig: Signature) -> proc_macro2::TokenStream {
+/// The signature the generated code builds, whichever parser read the declaration: with a `--help` flag, added after the
+/// declared flags like nu does for a `def`, and without the `...args` parameter nu adds to an `extern` declaring no rest.
+pub fn generated_signature(mut sig: Signature) -> Signature {
+    if !sig.named.iter().any(|flag| flag.long == "help") {
+        sig = sig.add_help();
+    }
+    sig.rest_positional = sig.rest_positional.filter(|rest| !command::is_default_rest(rest));
+    sig
+}
+
+/// Code building `sig`, which is already a [`generated_signature`].
+pub fn build_signature(name: &str, sig: Signature, interpolations: &Interpolations) -> proc_macro2::TokenStream {
+    let category = category_to_token(&sig.category);
     let description = (!sig.description.is_empty())
         .then(|| {
-            let desc = sig.description; 
+            let desc = interpolations.string_to_token(&sig.description); 
             quote! { .description(#desc) }
         });
     let extra_description = (!sig.extra_description.is_empty())
         .then(|| {
-            let desc = sig.extra_description; 
+            let desc = interpolations.string_to_token(&sig.extra_description); 
             quote! { .extra_description(#desc) }
         });
+    let search_terms = (!sig.search_terms.is_empty())
+        .then(|| {
+            let terms = &sig.search_terms;
+            quote! { .search_terms(vec![#(String::from(#terms)),*]) }
+        });
     let input_output_types = (!sig.input_output_types.is_empty())
         .then(|| {
             let iter = sig.input_output_types.iter()
@@ -291,43 +363,35 @@ pub fn build_signature(name: &str, sig: Signature) -> proc_macro2::TokenStream {
         });
         // sig.named(name, shape, desc, short)
     
-    let named = (!sig.named.is_empty()).then(||{
+    // always assigned, the `--help` flag `Signature::build` adds is in the generated signature's flags
+    let named = {
         let all_named_tokens = sig.named.iter()
-            .map(flag_to_token);
+            .map(|flag| flag_to_token(flag, interpolations));
         quote! { sig.named = vec![ #(#all_named_tokens),* ]; }
-    });
+    };
     let required_positional = (!sig.required_positional.is_empty()).then(|| {
         let req_pos_tokens = sig.required_positional.iter()
-            .map(positional_arg_to_token);
+            .map(|arg| positional_arg_to_token(arg, interpolations));
         quote! { sig.required_positional = vec![ #(#req_pos_tokens),* ]; }
     });
     let optional_positional = (!sig.optional_positional.is_empty()).then(|| {
         let opt_pos_tokens = sig.optional_positional
             .iter()
-            .map(positional_arg_to_token);
+            .map(|arg| positional_arg_to_token(arg, interpolations));
         quote! { sig.optional_positional = vec![ #(#opt_pos_tokens),* ]; }
     });
-    let default_rest_positional = nu_protocol::PositionalArg {
-        name: String::from("args"),
-        shape: nu_protocol::SyntaxShape::ExternalArgument,
-        desc: String::from("all other arguments to the command"),
-        var_id: None,
-        default_value: None,
-    };
-    let rest_positional = (sig.rest_positional.is_some())
-        .then_some(sig.rest_positional)
-        .flatten()
-        .filter(|rest_positional| rest_positional != &default_rest_positional)
+    let rest_positional = sig.rest_positional
         .map(|rest_positional| {
-            let rest_positional = positional_arg_to_token(&rest_positional);
-            quote! { sig.rest_positional = #rest_positional; }
+            let rest_positional = positional_arg_to_token(&rest_positional, interpolations);
+            quote! { sig.rest_positional = Some(#rest_positional); }
         });
     quote! {
         {
             let mut sig = nu_protocol::Signature::build(#name)
-                .category(nu_protocol::Category::Experimental)
+                .category(#category)
                 #description
                 #extra_description
+                #search_terms
                 #input_output_types;
             #named
             #required_positional
@@ -337,3 +401,19 @@ pub fn build_signature(name: &str, sig: Signature) -> proc_macro2::TokenStream {
         }
     }
 }
+
+pub fn build_examples(examples: &[Example]) -> proc_macro2::TokenStream {
+    let examples = examples
+        .iter()
+        .map(|example| {
+            let code = &example.example;
+            let description = &example.description;
+            let result = option_to_token(&example.result, value_to_token);
+            quote! { nu_protocol::Example {
+                example: #code,
+                description: #description,
+                result: #result,
+            } }
+        });
+    quote! { vec![#(#examples),*] }
+}
\ No newline at end of file
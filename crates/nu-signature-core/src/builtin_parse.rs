@@ -0,0 +1,1049 @@
+//! Hand-written parser for the subset of nu needed by the macro: `extern name [params]: in -> out`,
+//! or the same declaration with `def` (the body is skipped), optionally exported or nested in modules.
+//! The declaration can be preceded by `const` statements, used by the default values, and by `def` statements, used
+//! as completers.
+//!
+//! It produces the same `Signature` as the `nu-parser` path, without depending on the whole nushell parser.
+use std::collections::{HashMap, HashSet};
+
+use nu_protocol::ast::{CellPath, PathMember, RangeInclusion};
+use nu_protocol::{Flag, PositionalArg, Range, Record, Signature, Span, SyntaxShape, Type, Value};
+
+pub fn extract_declaration(content: &[u8]) -> Result<(String, Signature), String> {
+    let content = std::str::from_utf8(content).map_err(|e| format!("signature is not valid UTF-8: {}", e))?;
+    Parser::new(content).declaration()
+}
+
+/// Parses a nu constant expression, like a default value or an example result.
+pub fn parse_value(content: &[u8]) -> Result<Value, String> {
+    let content = std::str::from_utf8(content).map_err(|e| format!("value is not valid UTF-8: {}", e))?;
+    let mut parser = Parser::new(content);
+    parser.skip_whitespace();
+    let value = parser.value()?;
+    parser.skip_trivia();
+    if parser.peek().is_some() {
+        return Err(parser.error("only one value expected"));
+    }
+    Ok(value)
+}
+
+enum Arg {
+    Positional { arg: PositionalArg, required: bool },
+    Rest(PositionalArg),
+    Flag(Flag),
+}
+
+impl Arg {
+    fn desc_mut(&mut self) -> &mut String {
+        match self {
+            Arg::Positional { arg, .. } | Arg::Rest(arg) => &mut arg.desc,
+            Arg::Flag(flag) => &mut flag.desc,
+        }
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    /// Values and types of the `const` statements, which can be used as default values.
+    consts: HashMap<String, (Value, Type)>,
+    /// Names of the `def` statements preceding the declaration, which can be used as completers.
+    helpers: HashSet<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0, consts: HashMap::new(), helpers: HashSet::new() }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        if self.rest().starts_with(expected) {
+            self.pos += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", expected)))
+        }
+    }
+
+    fn error(&self, msg: impl std::fmt::Display) -> String {
+        self.error_at(self.pos, msg)
+    }
+
+    /// Error located at `pos`, the start of the token it is about.
+    fn error_at(&self, pos: usize, msg: impl std::fmt::Display) -> String {
+        let before = &self.src[..pos];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+        format!("{} (line {}, column {})", msg, line, column)
+    }
+
+    /// Skips spaces and tabs, but not new lines.
+    fn skip_inline_whitespace(&mut self) {
+        while let Some(' ' | '\t' | '\r') = self.peek() {
+            self.bump();
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.bump();
+        }
+    }
+
+    /// Skips whitespace, new lines, commas and comments.
+    fn skip_trivia(&mut self) {
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some('#') => {
+                    self.comment();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Reads a comment up to the end of the line, without the leading `#`.
+    fn comment(&mut self) -> &'a str {
+        let start = self.pos;
+        let end = self.rest().find('\n').map_or(self.src.len(), |i| self.pos + i);
+        self.pos = end;
+        &self.src[start + 1..end]
+    }
+
+    fn is_delimiter(c: char) -> bool {
+        c.is_whitespace() || "[](){}<>,:;#=?@\"'`".contains(c)
+    }
+
+    fn word(&mut self) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if Self::is_delimiter(c) {
+                break;
+            }
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some('"' | '\'' | '`') => self.string(),
+            _ => {
+                let word = self.word();
+                if word.is_empty() {
+                    return Err(self.error("missing function name"));
+                }
+                Ok(word.to_string())
+            }
+        }
+    }
+
+    /// Collects the doc comments directly preceding the declaration.
+    fn doc_comments(&mut self) -> Vec<&'a str> {
+        let mut comments = Vec::new();
+        loop {
+            self.skip_inline_whitespace();
+            match self.peek() {
+                Some('\n') => {
+                    self.bump();
+                    self.skip_inline_whitespace();
+                    if self.peek() == Some('\n') {
+                        // an empty line detaches the comments from the declaration
+                        comments.clear();
+                    }
+                }
+                Some('#') => {
+                    let start = self.pos;
+                    self.comment();
+                    comments.push(&self.src[start..self.pos]);
+                }
+                _ => break comments,
+            }
+        }
+    }
+
+    fn declaration(&mut self) -> Result<(String, Signature), String> {
+        let comments = loop {
+            let comments = self.doc_comments();
+            let start = self.pos;
+            if self.word() == "const" {
+                self.constant()?;
+                self.skip_inline_whitespace();
+                self.eat(";");
+                continue;
+            }
+            self.pos = start;
+            break comments;
+        };
+        let start = self.pos;
+        let mut keyword = self.word();
+        if keyword == "export" {
+            self.skip_inline_whitespace();
+            keyword = self.word();
+        }
+        let is_def = keyword == "def";
+        self.pos = start;
+        let (_, mut declarations) = self.statement(&comments)?;
+        self.skip_inline_whitespace();
+        self.eat(";");
+        let end = self.pos;
+        self.skip_trivia();
+        if self.peek().is_some() {
+            if !is_def {
+                return Err(self.error("only extern or def expression expected"));
+            }
+            // a `def` or `export def` followed by other statements is a helper, like a completer used by the declaration
+            self.pos = end;
+            self.helpers.extend(declarations.into_iter().map(|(name, _)| name));
+            return self.declaration();
+        }
+        match declarations.len() {
+            1 => Ok(declarations.remove(0)),
+            0 => Err("the module does not export any declaration".to_string()),
+            _ => Err("the module exports more than one declaration".to_string()),
+        }
+    }
+
+    /// Parses `const NAME = value` (after the `const` keyword), and stores its value for the default values.
+    fn constant(&mut self) -> Result<(), String> {
+        self.skip_inline_whitespace();
+        let name = self.word();
+        if name.is_empty() {
+            return Err(self.error("missing constant name"));
+        }
+        self.skip_inline_whitespace();
+        let declared_type = if self.eat(":") {
+            self.skip_inline_whitespace();
+            Some(self.shape()?.to_type())
+        } else {
+            None
+        };
+        self.skip_inline_whitespace();
+        self.expect("=")?;
+        self.skip_inline_whitespace();
+        let value_start = self.pos;
+        let value = self.value()?;
+        // like nu-parser, the constant has the type of its value, even with a broader declared type
+        let value_type = value.get_type();
+        if let Some(declared_type) = declared_type.filter(|declared_type| !type_compatible(declared_type, &value_type)) {
+            return Err(self.error_at(value_start, format!("constant `{}` of type `{}` does not match the type `{}`", name, value_type, declared_type)));
+        }
+        self.consts.insert(name.to_string(), (value, value_type));
+        Ok(())
+    }
+
+    /// Parses an `extern`, a `def` or a `module`, exported or not.
+    /// Returns whether it is exported and the declarations it contains, with their full names.
+    fn statement(&mut self, comments: &[&str]) -> Result<(bool, Vec<(String, Signature)>), String> {
+        let mut start = self.pos;
+        let mut keyword = self.word();
+        let exported = keyword == "export";
+        if exported {
+            self.skip_inline_whitespace();
+            start = self.pos;
+            keyword = self.word();
+        }
+        let declarations = match keyword {
+            "extern" => vec![self.command(comments, false)?],
+            "def" => vec![self.command(comments, true)?],
+            "module" => self.module()?,
+            _ => return Err(self.error_at(start, format!("extern, def or module expression expected, found `{}`", keyword))),
+        };
+        Ok((exported, declarations))
+    }
+
+    fn module(&mut self) -> Result<Vec<(String, Signature)>, String> {
+        self.skip_inline_whitespace();
+        let module_name = self.name()?;
+        self.skip_whitespace();
+        self.expect("{")?;
+        let mut declarations = Vec::new();
+        loop {
+            let comments = self.doc_comments();
+            if self.eat("}") {
+                break;
+            }
+            if self.eat(";") {
+                continue;
+            }
+            if self.peek().is_none() {
+                return Err(self.error("missing `}` at the end of the module"));
+            }
+            let (exported, inner) = self.statement(&comments)?;
+            if exported {
+                declarations.extend(inner.into_iter().map(|(name, mut sig)| {
+                    let name = format!("{} {}", module_name, name);
+                    sig.name.clone_from(&name);
+                    (name, sig)
+                }));
+            }
+        }
+        Ok(declarations)
+    }
+
+    fn command(&mut self, comments: &[&str], is_def: bool) -> Result<(String, Signature), String> {
+        let mut wrapped = false;
+        loop {
+            self.skip_inline_whitespace();
+            if is_def && self.eat("--env") {
+                continue;
+            }
+            if is_def && self.eat("--wrapped") {
+                wrapped = true;
+                continue;
+            }
+            break;
+        }
+        let name = self.name()?;
+
+        let mut sig = Signature::new(name.clone());
+        (sig.description, sig.extra_description) = build_desc(comments);
+        sig.allows_unknown_args = !is_def || wrapped;
+
+        self.skip_whitespace();
+        self.signature(&mut sig)?;
+        self.skip_inline_whitespace();
+        if self.eat(":") {
+            self.skip_whitespace();
+            sig.input_output_types = self.input_output_types()?;
+        }
+        if is_def {
+            self.skip_whitespace();
+            self.body()?;
+            // like nu, which adds the flag after the declared ones
+            if !wrapped {
+                sig = sig.add_help();
+            }
+        }
+
+        if !is_def && sig.rest_positional.is_none() {
+            sig.rest_positional = Some(PositionalArg {
+                name: String::from("args"),
+                shape: SyntaxShape::ExternalArgument,
+                desc: String::from("all other arguments to the command"),
+                var_id: None,
+                default_value: None,
+            });
+        }
+        Ok((name, sig))
+    }
+
+    /// Skips the body of a `def`, which is not needed to build the signature.
+    fn body(&mut self) -> Result<(), String> {
+        self.expect("{")?;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.peek() {
+                None => return Err(self.error("missing `}` at the end of the body")),
+                Some('"' | '\'' | '`') => {
+                    self.string()?;
+                }
+                Some('#') => {
+                    self.comment();
+                }
+                Some(c) => {
+                    match c {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    self.bump();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn signature(&mut self, sig: &mut Signature) -> Result<(), String> {
+        self.expect("[")?;
+        let mut args: Vec<Arg> = Vec::new();
+        loop {
+            loop {
+                self.skip_whitespace();
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            match self.peek() {
+                None => return Err(self.error("missing `]` at the end of the parameters")),
+                Some(']') => {
+                    self.bump();
+                    break;
+                }
+                Some('#') => {
+                    let comment = self.comment().trim();
+                    if let Some(last) = args.last_mut() {
+                        let desc = last.desc_mut();
+                        if !desc.is_empty() {
+                            desc.push('\n');
+                        }
+                        desc.push_str(comment);
+                    }
+                }
+                Some(_) => {
+                    let arg = self.parameter()?;
+                    args.push(arg);
+                }
+            }
+        }
+
+        for arg in args {
+            match arg {
+                Arg::Positional { arg, required: true } => {
+                    if !sig.optional_positional.is_empty() {
+                        return Err(format!("required positional parameter `{}` found after an optional one", arg.name));
+                    }
+                    sig.required_positional.push(arg)
+                }
+                Arg::Positional { arg, required: false } => sig.optional_positional.push(arg),
+                Arg::Rest(arg) => {
+                    if sig.rest_positional.is_some() {
+                        return Err(format!("multiple rest parameters: `{}`", arg.name));
+                    }
+                    sig.rest_positional = Some(arg)
+                }
+                Arg::Flag(flag) => {
+                    let duplicated = sig.named.iter().any(|f| {
+                        (!flag.long.is_empty() && f.long == flag.long) || (flag.short.is_some() && f.short == flag.short)
+                    });
+                    if duplicated {
+                        return Err(format!("duplicated flag: `{}`", flag.long));
+                    }
+                    sig.named.push(flag)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn parameter(&mut self) -> Result<Arg, String> {
+        let mut arg = if self.eat("--") {
+            let long = self.word();
+            if long.is_empty() {
+                return Err(self.error("missing flag name"));
+            }
+            let short = if self.eat("(") {
+                self.expect("-")?;
+                let short = self.short_flag()?;
+                self.expect(")")?;
+                Some(short)
+            } else {
+                None
+            };
+            Arg::Flag(Flag {
+                long: long.to_string(),
+                short,
+                arg: None,
+                required: false,
+                desc: String::new(),
+                var_id: None,
+                default_value: None,
+            })
+        } else if self.eat("-") {
+            let short = self.short_flag()?;
+            Arg::Flag(Flag {
+                long: String::new(),
+                short: Some(short),
+                arg: None,
+                required: false,
+                desc: String::new(),
+                var_id: None,
+                default_value: None,
+            })
+        } else if self.eat("...") {
+            let name = self.parameter_name()?;
+            Arg::Rest(PositionalArg {
+                name,
+                desc: String::new(),
+                shape: SyntaxShape::Any,
+                var_id: None,
+                default_value: None,
+            })
+        } else {
+            let name = self.parameter_name()?;
+            let required = !self.eat("?");
+            Arg::Positional {
+                arg: PositionalArg {
+                    name,
+                    desc: String::new(),
+                    shape: SyntaxShape::Any,
+                    var_id: None,
+                    default_value: None,
+                },
+                required,
+            }
+        };
+
+        self.skip_inline_whitespace();
+        let shape_start = self.pos;
+        let declared_shape = if self.eat(":") {
+            self.skip_inline_whitespace();
+            Some(self.shape()?)
+        } else {
+            None
+        };
+        if let Some(shape) = &declared_shape {
+            match &mut arg {
+                Arg::Positional { arg, .. } | Arg::Rest(arg) => arg.shape = shape.clone(),
+                Arg::Flag(flag) if *shape == SyntaxShape::Boolean => {
+                    return Err(self.error_at(shape_start, format!("type annotations are not allowed for the boolean switch `--{}`", flag.long)));
+                }
+                Arg::Flag(flag) => flag.arg = Some(shape.clone()),
+            }
+        }
+
+        self.skip_inline_whitespace();
+        if self.eat("=") {
+            self.skip_inline_whitespace();
+            let value_start = self.pos;
+            let (value, value_type) = self.typed_value()?;
+            if let Some(shape) = &declared_shape {
+                if !type_compatible(&shape.to_type(), &value_type) {
+                    return Err(self.error_at(value_start, format!("default value of type `{}` does not match the type `{}`", value_type, shape)));
+                }
+            }
+            match &mut arg {
+                Arg::Positional { arg, required } => {
+                    if declared_shape.is_none() {
+                        arg.shape = value_type.to_shape();
+                    }
+                    arg.default_value = Some(value);
+                    *required = false;
+                }
+                Arg::Flag(flag) => {
+                    if declared_shape.is_none() {
+                        flag.arg = Some(value_type.to_shape());
+                    }
+                    flag.default_value = Some(value);
+                }
+                Arg::Rest(arg) => return Err(format!("rest parameter `{}` cannot have a default value", arg.name)),
+            }
+        }
+        Ok(arg)
+    }
+
+    fn parameter_name(&mut self) -> Result<String, String> {
+        let name = self.word();
+        if name.is_empty() {
+            return Err(self.error("missing parameter name"));
+        }
+        Ok(name.to_string())
+    }
+
+    fn short_flag(&mut self) -> Result<char, String> {
+        let start = self.pos;
+        let short = self.word();
+        let mut chars = short.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(self.error_at(start, format!("short flag must be a single character: `-{}`", short))),
+        }
+    }
+
+    fn input_output_types(&mut self) -> Result<Vec<(Type, Type)>, String> {
+        if !self.eat("[") {
+            return Ok(vec![self.input_output_type()?]);
+        }
+        let mut types = Vec::new();
+        loop {
+            self.skip_trivia();
+            if self.eat("]") {
+                break Ok(types);
+            }
+            if self.peek().is_none() {
+                break Err(self.error("missing `]` at the end of the input/output types"));
+            }
+            types.push(self.input_output_type()?);
+        }
+    }
+
+    fn input_output_type(&mut self) -> Result<(Type, Type), String> {
+        let input = self.shape()?.to_type();
+        self.skip_whitespace();
+        self.expect("->")?;
+        self.skip_whitespace();
+        let output = self.shape()?.to_type();
+        Ok((input, output))
+    }
+
+    fn shape(&mut self) -> Result<SyntaxShape, String> {
+        let start = self.pos;
+        let name = self.word();
+        let shape = match name {
+            "any" => SyntaxShape::Any,
+            "binary" => SyntaxShape::Binary,
+            "block" => return Err(self.error_at(start, "blocks are not supported as first-class values")),
+            "bool" => SyntaxShape::Boolean,
+            "cell-path" => SyntaxShape::CellPath,
+            "closure" => SyntaxShape::Closure(None),
+            "datetime" => SyntaxShape::DateTime,
+            "directory" => SyntaxShape::Directory,
+            "duration" => SyntaxShape::Duration,
+            "error" => SyntaxShape::Error,
+            "float" => SyntaxShape::Float,
+            "filesize" => SyntaxShape::Filesize,
+            "glob" => SyntaxShape::GlobPattern,
+            "int" => SyntaxShape::Int,
+            "list" => {
+                if self.eat("<") {
+                    self.skip_whitespace();
+                    let inner = if self.peek() == Some('>') { SyntaxShape::Any } else { self.shape()? };
+                    self.skip_whitespace();
+                    self.expect(">")?;
+                    SyntaxShape::List(Box::new(inner))
+                } else {
+                    SyntaxShape::List(Box::new(SyntaxShape::Any))
+                }
+            }
+            "nothing" => SyntaxShape::Nothing,
+            "number" => SyntaxShape::Number,
+            "path" => SyntaxShape::Filepath,
+            "range" => SyntaxShape::Range,
+            "record" => SyntaxShape::Record(self.collection_shape()?),
+            "string" => SyntaxShape::String,
+            "table" => SyntaxShape::Table(self.collection_shape()?),
+            "" => return Err(self.error("missing type")),
+            _ => return Err(self.error_at(start, format!("unknown type: `{}`", name))),
+        };
+        if self.eat("@") {
+            // nu-parser reads the completer as part of the type name, which the collection types don't allow
+            if matches!(shape, SyntaxShape::List(_) | SyntaxShape::Record(_) | SyntaxShape::Table(_)) {
+                return Err(self.error_at(start, format!("the type `{}` cannot have a completer", shape)));
+            }
+            let completer_start = self.pos;
+            let completer = self.name()?;
+            if !self.helpers.contains(&completer) {
+                return Err(self.error_at(completer_start, format!("unknown completer command: `{}`", completer)));
+            }
+            // the id of the completer only makes sense in the parser's engine state, the generated code doesn't keep it
+            return Ok(SyntaxShape::CompleterWrapper(Box::new(shape), Default::default()));
+        }
+        Ok(shape)
+    }
+
+    fn collection_shape(&mut self) -> Result<Vec<(String, SyntaxShape)>, String> {
+        let mut entries = Vec::new();
+        if !self.eat("<") {
+            return Ok(entries);
+        }
+        loop {
+            self.skip_trivia();
+            if self.eat(">") {
+                break Ok(entries);
+            }
+            let key = match self.peek() {
+                None => break Err(self.error("missing `>`")),
+                Some('"' | '\'' | '`') => self.string()?,
+                Some(_) => {
+                    let key = self.word();
+                    if key.is_empty() {
+                        return Err(self.error("missing column name"));
+                    }
+                    key.to_string()
+                }
+            };
+            self.skip_whitespace();
+            let shape = if self.eat(":") {
+                self.skip_whitespace();
+                self.shape()?
+            } else {
+                SyntaxShape::Any
+            };
+            entries.push((key, shape));
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        match self.bump() {
+            Some('"') => {
+                let mut res = String::new();
+                loop {
+                    match self.bump() {
+                        None => break Err(self.error("unterminated string")),
+                        Some('"') => break Ok(res),
+                        Some('\\') => {
+                            let escaped = match self.bump() {
+                                Some('"') => '"',
+                                Some('\'') => '\'',
+                                Some('\\') => '\\',
+                                Some('/') => '/',
+                                Some('(') => '(',
+                                Some(')') => ')',
+                                Some('{') => '{',
+                                Some('}') => '}',
+                                Some('$') => '$',
+                                Some('^') => '^',
+                                Some('#') => '#',
+                                Some('|') => '|',
+                                Some('~') => '~',
+                                Some('a') => '\x07',
+                                Some('b') => '\x08',
+                                Some('e') => '\x1b',
+                                Some('f') => '\x0c',
+                                Some('n') => '\n',
+                                Some('r') => '\r',
+                                Some('t') => '\t',
+                                Some('0') => '\0',
+                                Some('u') => {
+                                    self.expect("{")?;
+                                    let start = self.pos;
+                                    while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                                        self.bump();
+                                    }
+                                    let code = u32::from_str_radix(&self.src[start..self.pos], 16).ok().and_then(char::from_u32);
+                                    self.expect("}")?;
+                                    code.ok_or_else(|| self.error("invalid unicode escape"))?
+                                }
+                                c => return Err(self.error(format!("invalid escape sequence: \\{}", c.unwrap_or(' ')))),
+                            };
+                            res.push(escaped);
+                        }
+                        Some(c) => res.push(c),
+                    }
+                }
+            }
+            Some(quote @ ('\'' | '`')) => {
+                let start = self.pos;
+                let len = self.rest().find(quote).ok_or_else(|| self.error("unterminated string"))?;
+                self.pos += len + 1;
+                Ok(self.src[start..start + len].to_string())
+            }
+            _ => Err(self.error("string expected")),
+        }
+    }
+
+    /// Parses a value, with the type of the expression: the declared type for a constant, the type of the value otherwise.
+    fn typed_value(&mut self) -> Result<(Value, Type), String> {
+        let start = self.pos;
+        if self.eat("$") {
+            if self.peek() == Some('.') {
+                let value = self.cell_path()?;
+                return Ok((value, Type::CellPath));
+            }
+            let mut path = self.word().split('.');
+            let name = path.next().unwrap_or_default();
+            let (mut value, value_type) = self.consts.get(name).cloned().ok_or_else(|| self.error_at(start, format!("unknown constant: `${}`", name)))?;
+            let mut path = path.peekable();
+            if path.peek().is_none() {
+                return Ok((value, value_type));
+            }
+            // like nu-parser, which doesn't follow the cell path for the type of the expression
+            for member in path {
+                let field = match &value {
+                    Value::Record { val, .. } => val.get(member).cloned(),
+                    Value::List { vals, .. } => member.parse::<usize>().ok().and_then(|i| vals.get(i).cloned()),
+                    _ => None,
+                };
+                value = field.ok_or_else(|| self.error_at(start, format!("`{}` not found in `${}`", member, name)))?;
+            }
+            return Ok((value, Type::Any));
+        }
+        let value = self.value()?;
+        let value_type = value.get_type();
+        Ok((value, value_type))
+    }
+
+    /// Parses the members of a cell path literal, after its `$`: `.name`, `.0`, optionally followed by `?`.
+    fn cell_path(&mut self) -> Result<Value, String> {
+        let span = Span::unknown();
+        let mut members = Vec::new();
+        while self.eat(".") {
+            let start = self.pos;
+            while self.peek().is_some_and(|c| c != '.' && !Self::is_delimiter(c)) {
+                self.bump();
+            }
+            let member = &self.src[start..self.pos];
+            if member.is_empty() {
+                return Err(self.error("missing cell path member"));
+            }
+            let optional = self.eat("?");
+            members.push(match member.parse::<usize>() {
+                Ok(index) => PathMember::int(index, optional, span),
+                Err(_) => PathMember::string(member.to_string(), optional, span),
+            });
+        }
+        Ok(Value::cell_path(CellPath { members }, span))
+    }
+
+    /// Parses the bytes of a binary literal, after its `0x`, `0o` or `0b` prefix.
+    fn binary(&mut self, base: u32, digits_per_byte: usize) -> Result<Value, String> {
+        let start = self.pos;
+        self.expect("[")?;
+        let end = self.rest().find(']').ok_or_else(|| self.error("missing `]` at the end of the binary"))?;
+        let mut digits: String = self.rest()[..end].chars().filter(|c| !c.is_whitespace() && *c != ',').collect();
+        self.pos += end + 1;
+        // like nu, the missing leading zeros of the first byte are implied
+        let padding = (digits_per_byte - digits.len() % digits_per_byte) % digits_per_byte;
+        digits.insert_str(0, &"0".repeat(padding));
+        let bytes = digits.as_bytes()
+            .chunks(digits_per_byte)
+            .map(|byte| std::str::from_utf8(byte).ok().and_then(|byte| u8::from_str_radix(byte, base).ok()))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| self.error_at(start, "not a binary value"))?;
+        Ok(Value::binary(bytes, Span::unknown()))
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        let span = Span::unknown();
+        match self.peek() {
+            Some('"' | '\'' | '`') => Ok(Value::string(self.string()?, span)),
+            Some('$') => self.typed_value().map(|(value, _)| value),
+            Some('[') => {
+                self.bump();
+                let mut values = Vec::new();
+                loop {
+                    self.skip_trivia();
+                    if self.eat("]") {
+                        break Ok(Value::list(values, span));
+                    }
+                    if self.peek().is_none() {
+                        break Err(self.error("missing `]` at the end of the list"));
+                    }
+                    values.push(self.value()?);
+                }
+            }
+            Some('{') => {
+                self.bump();
+                let mut record = Record::new();
+                loop {
+                    self.skip_trivia();
+                    if self.eat("}") {
+                        break Ok(Value::record(record, span));
+                    }
+                    let key = match self.peek() {
+                        None => break Err(self.error("missing `}` at the end of the record")),
+                        Some('"' | '\'' | '`') => self.string()?,
+                        Some(_) => self.word().to_string(),
+                    };
+                    self.skip_whitespace();
+                    self.expect(":")?;
+                    self.skip_whitespace();
+                    let value = self.value()?;
+                    record.push(key, value);
+                }
+            }
+            _ => {
+                let start = self.pos;
+                let word = self.word();
+                if word.is_empty() {
+                    return Err(self.error("value expected"));
+                }
+                match word {
+                    "0x" if self.peek() == Some('[') => return self.binary(16, 2),
+                    "0o" if self.peek() == Some('[') => return self.binary(8, 3),
+                    "0b" if self.peek() == Some('[') => return self.binary(2, 8),
+                    _ => {}
+                }
+                range(word, span)
+                    .or_else(|| literal(word, span))
+                    .ok_or_else(|| self.error_at(start, format!("unsupported default value: `{}`", word)))
+            }
+        }
+    }
+}
+
+/// Parses a bare word the way nu would for a constant: numbers, sizes, durations, booleans, null or a bare string.
+fn literal(word: &str, span: Span) -> Option<Value> {
+    if is_datetime_start(word) {
+        return datetime(word, span);
+    }
+    match word {
+        "true" => return Some(Value::bool(true, span)),
+        "false" => return Some(Value::bool(false, span)),
+        "null" => return Some(Value::nothing(span)),
+        _ => {}
+    }
+    if let Some(int) = parse_int(word) {
+        return Some(Value::int(int, span));
+    }
+    if word.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+' || c == '.') {
+        if let Ok(float) = word.parse::<f64>() {
+            return Some(Value::float(float, span));
+        }
+        let unit_start = word.find(|c: char| c.is_alphabetic())?;
+        let (number, unit) = word.split_at(unit_start);
+        let number = number.parse::<f64>().ok()?;
+        if let Some(factor) = filesize_unit(unit) {
+            return Some(Value::filesize((number * factor as f64) as i64, span));
+        }
+        if let Some(factor) = duration_unit(unit) {
+            return Some(Value::duration((number * factor as f64) as i64, span));
+        }
+        return None;
+    }
+    if word.starts_with('$') {
+        return None;
+    }
+    Some(Value::string(word, span))
+}
+
+/// Parses `start..end` or `start..next..end`, where the bounds are optional numbers, like nu does. The `..<` ranges are
+/// not accepted in a signature, where `<` starts a type argument.
+fn range(word: &str, span: Span) -> Option<Value> {
+    let (start, end) = word.rsplit_once("..")?;
+    let (start, next) = match start.split_once("..") {
+        Some((start, next)) => (start, next),
+        None => (start, ""),
+    };
+    let bound = |bound: &str| match bound {
+        "" => Some(Value::nothing(span)),
+        bound => literal(bound, span).filter(|value| matches!(value, Value::Int { .. } | Value::Float { .. })),
+    };
+    let range = Range::new(bound(start)?, bound(next)?, bound(end)?, RangeInclusion::Inclusive, span).ok()?;
+    Some(Value::range(range, span))
+}
+
+/// Whether `word` starts like a date, with four digits followed by `-`, which nu parses as a date time.
+fn is_datetime_start(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    bytes.len() >= 6 && bytes[..4].iter().all(u8::is_ascii_digit) && bytes[4] == b'-'
+}
+
+/// Parses a RFC 3339 date time, a date or a date time without offset, in UTC, like nu does.
+fn datetime(word: &str, span: Span) -> Option<Value> {
+    [word.to_string(), format!("{}T00:00:00+00:00", word), format!("{}+00:00", word)]
+        .iter()
+        .find_map(|datetime| chrono::DateTime::parse_from_rfc3339(datetime).ok())
+        .map(|datetime| Value::date(datetime, span))
+}
+
+fn parse_int(word: &str) -> Option<i64> {
+    let (negative, digits) = match word.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, word.strip_prefix('+').unwrap_or(word)),
+    };
+    let digits = digits.replace('_', "");
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if let Some(octal) = digits.strip_prefix("0o") {
+        i64::from_str_radix(octal, 8).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2).ok()?
+    } else if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+        digits.parse::<i64>().ok()?
+    } else {
+        return None;
+    };
+    Some(if negative { -value } else { value })
+}
+
+fn filesize_unit(unit: &str) -> Option<i64> {
+    let factor = match unit.to_ascii_lowercase().as_str() {
+        "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000_i64.pow(2),
+        "gb" => 1000_i64.pow(3),
+        "tb" => 1000_i64.pow(4),
+        "pb" => 1000_i64.pow(5),
+        "eb" => 1000_i64.pow(6),
+        "kib" => 1024,
+        "mib" => 1024_i64.pow(2),
+        "gib" => 1024_i64.pow(3),
+        "tib" => 1024_i64.pow(4),
+        "pib" => 1024_i64.pow(5),
+        "eib" => 1024_i64.pow(6),
+        _ => return None,
+    };
+    Some(factor)
+}
+
+fn duration_unit(unit: &str) -> Option<i64> {
+    let factor = match unit {
+        "ns" => 1,
+        "us" | "µs" => 1000,
+        "ms" => 1000_i64.pow(2),
+        "sec" => 1000_i64.pow(3),
+        "min" => 60 * 1000_i64.pow(3),
+        "hr" => 60 * 60 * 1000_i64.pow(3),
+        "day" => 24 * 60 * 60 * 1000_i64.pow(3),
+        "wk" => 7 * 24 * 60 * 60 * 1000_i64.pow(3),
+        _ => return None,
+    };
+    Some(factor)
+}
+
+/// Same as `nu_parser::type_compatible`: the records and tables are compatible when one of them has no columns, or when
+/// the actual one has every expected column.
+fn type_compatible(expected: &Type, actual: &Type) -> bool {
+    let columns_compatible = |expected: &[(String, Type)], actual: &[(String, Type)]| {
+        expected.is_empty()
+            || actual.is_empty()
+            || expected.iter().all(|(column, expected)| {
+                actual.iter().any(|(name, actual)| name == column && type_compatible(expected, actual))
+            })
+    };
+    match (expected, actual) {
+        (Type::List(expected), Type::List(actual)) => type_compatible(expected, actual),
+        (Type::ListStream, Type::List(_)) | (Type::List(_), Type::ListStream) => true,
+        (Type::List(inner), Type::Table(columns)) | (Type::Table(columns), Type::List(inner)) => match &**inner {
+            Type::Any => true,
+            Type::Record(fields) if matches!(expected, Type::List(_)) => columns_compatible(fields, columns),
+            Type::Record(fields) => columns_compatible(columns, fields),
+            _ => false,
+        },
+        (Type::Number, Type::Int | Type::Float) | (Type::Int | Type::Float, Type::Number) => true,
+        (Type::Closure, Type::Block) => true,
+        (Type::Any, _) | (_, Type::Any) => true,
+        (Type::Record(expected), Type::Record(actual)) | (Type::Table(expected), Type::Table(actual)) => {
+            columns_compatible(expected, actual)
+        }
+        (Type::Glob, Type::String) => true,
+        (expected, actual) => expected == actual,
+    }
+}
+
+/// Same as `nu_parser::build_desc`: the first paragraph is the description, the rest is the extra description.
+fn build_desc(comment_lines: &[&str]) -> (String, String) {
+    let mut description = String::new();
+    let mut num_spaces = 0;
+    for (i, contents) in comment_lines.iter().enumerate() {
+        let contents = contents.as_bytes();
+        let mut pos = 1;
+        while pos < contents.len() && (i == 0 || pos < num_spaces) && contents[pos] == b' ' {
+            pos += 1;
+        }
+        if i == 0 {
+            num_spaces = pos;
+        }
+        if !description.is_empty() {
+            description.push('\n');
+        }
+        description.push_str(&String::from_utf8_lossy(&contents[pos..]));
+    }
+    if let Some((brief_desc, extra_desc)) = description.split_once("\r\n\r\n") {
+        (brief_desc.to_string(), extra_desc.to_string())
+    } else if let Some((brief_desc, extra_desc)) = description.split_once("\n\n") {
+        (brief_desc.to_string(), extra_desc.to_string())
+    } else {
+        (description, String::default())
+    }
+}
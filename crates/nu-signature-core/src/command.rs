@@ -0,0 +1,561 @@
+//! Whole plugin commands generated from the nu declaration:
+//! `plugin_command! { struct HelloWorld for MyPlugin; r#"extern hello_world [...]"# }`.
+//!
+//! The struct and its `PluginCommand` or `SimplePluginCommand` implementation are generated, `run` calls a function
+//! written by hand, named after the command, with the arguments already converted to Rust types.
+//!
+//! Commands built into nushell use the attribute form instead, `#[engine_command(r#"extern ..."#)]` on the struct,
+//! which implements `nu_protocol::engine::Command`.
+//!
+//! With `#[command(dispatch)]`, `run` checks the input against the declared input types and calls the method of the
+//! struct handling it, `on_string` for a string, instead of the function. With `#[command(check_output)]`, debug builds check that the
+//! output has a type declared for the input.
+use nu_protocol::{Signature, SyntaxShape};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    parse::{Parse, ParseStream},
+    Attribute, DeriveInput, Path, Token, Type, Visibility,
+};
+
+use crate::build_sig;
+use crate::help;
+use crate::rust_types::{self, RustTypes};
+use crate::Source;
+
+/// Trait implemented by the generated command.
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    /// `SimplePluginCommand`, the input and the output are `Value`s.
+    Simple,
+    /// `PluginCommand`, the input and the output are `PipelineData`.
+    Pipeline,
+}
+
+/// Options given with `#[command(...)]` before the struct.
+#[derive(Default)]
+struct Options {
+    /// Function called by `run`, instead of the one named after the command.
+    run: Option<Path>,
+    /// Chosen from the input/output types when not given.
+    kind: Option<Kind>,
+    /// Name of the struct generated for the record or table output, returned by the `run` function.
+    output: Option<Ident>,
+    /// `run` calls the method of the struct handling the input type, `on_string` for a string input, instead of a
+    /// function handling any input.
+    dispatch: bool,
+    /// In debug builds, `run` checks that the output has a type declared for the input.
+    check_output: bool,
+}
+
+impl Options {
+    fn parse_attribute(&mut self, attr: &Attribute) -> syn::Result<()> {
+        attr.parse_nested_meta(|meta| {
+            let kind = if meta.path.is_ident("run") {
+                self.run = Some(meta.value()?.parse()?);
+                return Ok(());
+            } else if meta.path.is_ident("output") {
+                self.output = Some(meta.value()?.parse()?);
+                return Ok(());
+            } else if meta.path.is_ident("dispatch") {
+                self.dispatch = true;
+                return Ok(());
+            } else if meta.path.is_ident("check_output") {
+                self.check_output = true;
+                return Ok(());
+            } else if meta.path.is_ident("simple") {
+                Kind::Simple
+            } else if meta.path.is_ident("pipeline") {
+                Kind::Pipeline
+            } else {
+                return Err(meta.error("unknown command option, expected `run`, `simple`, `pipeline`, `output`, `dispatch` or `check_output`"));
+            };
+            if self.kind.is_some_and(|previous| previous != kind) {
+                return Err(meta.error("a command can't be both `simple` and `pipeline`"));
+            }
+            self.kind = Some(kind);
+            Ok(())
+        })
+    }
+}
+
+/// `[#[command(...)]] [pub] struct Name for Plugin;` followed by the input of the `signature` macro.
+pub struct CommandItem {
+    attrs: Vec<Attribute>,
+    options: Options,
+    vis: Visibility,
+    ident: Ident,
+    plugin: Type,
+    pub source: TokenStream,
+}
+
+impl Parse for CommandItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attrs = Vec::new();
+        let mut options = Options::default();
+        for attr in input.call(Attribute::parse_outer)? {
+            if attr.path().is_ident("command") {
+                options.parse_attribute(&attr)?;
+            } else {
+                attrs.push(attr);
+            }
+        }
+        let vis = input.parse()?;
+        input.parse::<Token![struct]>()?;
+        let ident = input.parse()?;
+        input.parse::<Token![for]>()?;
+        let plugin = input.parse()?;
+        input.parse::<Token![;]>()?;
+        let source = input.parse()?;
+        Ok(Self { attrs, options, vis, ident, plugin, source })
+    }
+}
+
+pub fn plugin_command(item: &CommandItem, source: &Source) -> TokenStream {
+    let CommandItem { attrs, vis, ident, plugin, .. } = item;
+    let attrs = with_documentation(attrs.clone(), source);
+    let methods = common_methods(source);
+    let mut types = RustTypes::new(vis);
+    let args = match arguments(&mut types, ident, source) {
+        Ok(args) => args,
+        Err(e) => return quote! { compile_error!(#e); },
+    };
+    let param_items = types.items;
+    let kind = item.options.kind.unwrap_or_else(|| default_kind(&source.signature));
+    let (command_trait, input, output) = match kind {
+        Kind::Simple => (quote! { SimplePluginCommand }, quote! { &nu_protocol::Value }, quote! { nu_protocol::Value }),
+        Kind::Pipeline => (quote! { PluginCommand }, quote! { nu_protocol::PipelineData }, quote! { nu_protocol::PipelineData }),
+    };
+    let (output_items, convert_output) = match output_structs(&item.options, &source.signature, vis, kind) {
+        Ok(output) => output,
+        Err(e) => return e,
+    };
+    let args = quote! { plugin, engine, call, input, #(#args),* };
+    let result = quote! { Result<#output, nu_protocol::LabeledError> };
+    let body = run_body(&item.options, source, kind, None, args, convert_output)
+        .and_then(|body| check_output(&item.options, source, kind, body, result));
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => return e,
+    };
+
+    quote! {
+        #(#attrs)*
+        #vis struct #ident;
+
+        #(#output_items)*
+
+        #(#param_items)*
+
+        impl nu_plugin::#command_trait for #ident {
+            type Plugin = #plugin;
+
+            #methods
+
+            fn run(
+                &self,
+                plugin: &Self::Plugin,
+                engine: &nu_plugin::EngineInterface,
+                call: &nu_plugin::EvaluatedCall,
+                input: #input,
+            ) -> Result<#output, nu_protocol::LabeledError> {
+                #body
+            }
+        }
+    }
+}
+
+/// Implements `nu_protocol::engine::Command` for the struct the attribute is on. `run` calls a function written by
+/// hand, named after the command, with the struct and the arguments of `run`.
+pub fn engine_command(mut item: DeriveInput, source: &Source) -> TokenStream {
+    let mut options = Options::default();
+    let mut attrs = Vec::new();
+    for attr in std::mem::take(&mut item.attrs) {
+        if attr.path().is_ident("command") {
+            if let Err(e) = options.parse_attribute(&attr) {
+                return e.to_compile_error();
+            }
+        } else {
+            attrs.push(attr);
+        }
+    }
+    item.attrs = with_documentation(attrs, source);
+    if options.kind.is_some() {
+        return quote! { compile_error!("`simple` and `pipeline` only apply to plugin commands"); };
+    }
+    let methods = common_methods(source);
+    let (output_items, convert_output) = match output_structs(&options, &source.signature, &item.vis, Kind::Pipeline) {
+        Ok(output) => output,
+        Err(e) => return e,
+    };
+    let args = quote! { engine_state, stack, call, input };
+    let result = quote! { Result<nu_protocol::PipelineData, nu_protocol::ShellError> };
+    let body = run_body(&options, source, Kind::Pipeline, Some(quote! { self, }), args, convert_output)
+        .and_then(|body| check_output(&options, source, Kind::Pipeline, body, result));
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => return e,
+    };
+    let ident = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    quote! {
+        #item
+
+        #(#output_items)*
+
+        impl #impl_generics nu_protocol::engine::Command for #ident #ty_generics #where_clause {
+            #methods
+
+            fn run(
+                &self,
+                engine_state: &nu_protocol::engine::EngineState,
+                stack: &mut nu_protocol::engine::Stack,
+                call: &nu_protocol::engine::Call,
+                input: nu_protocol::PipelineData,
+            ) -> Result<nu_protocol::PipelineData, nu_protocol::ShellError> {
+                #body
+            }
+        }
+    }
+}
+
+/// Attributes of the struct, with the documentation generated from the declaration after the one written on it: the
+/// description if there is none, and the usage, flags, parameters and input/output types like nushell's help.
+fn with_documentation(attrs: Vec<Attribute>, source: &Source) -> Vec<Attribute> {
+    let (mut docs, others): (Vec<_>, Vec<_>) = attrs.into_iter().partition(|attr| attr.path().is_ident("doc"));
+    let description = &source.signature.description;
+    if docs.is_empty() && !description.is_empty() {
+        docs.push(syn::parse_quote! { #[doc = #description] });
+        docs.push(syn::parse_quote! { #[doc = ""] });
+    }
+    let usage = format!("```text\n{}```", help::usage(&source.signature));
+    docs.push(syn::parse_quote! { #[doc = #usage] });
+    docs.into_iter().chain(others).collect()
+}
+
+/// Methods shared by the command traits: the name, the descriptions, the search terms, the signature and the examples.
+fn common_methods(source: &Source) -> TokenStream {
+    let name = &source.name;
+    let sig = &source.signature;
+    let description = source.interpolations.static_str_to_token(&sig.description);
+    let extra_description = (!sig.extra_description.is_empty()).then(|| {
+        let extra_description = source.interpolations.static_str_to_token(&sig.extra_description);
+        quote! {
+            fn extra_description(&self) -> &str {
+                #extra_description
+            }
+        }
+    });
+    let search_terms = (!sig.search_terms.is_empty()).then(|| {
+        let terms = &sig.search_terms;
+        quote! {
+            fn search_terms(&self) -> Vec<&str> {
+                vec![#(#terms),*]
+            }
+        }
+    });
+    let signature = build_sig::build_signature(name, sig.clone(), &source.interpolations);
+    let examples = build_sig::build_examples(&source.attributes.examples);
+
+    quote! {
+        fn name(&self) -> &str {
+            #name
+        }
+
+        fn description(&self) -> &str {
+            #description
+        }
+
+        #extra_description
+
+        #search_terms
+
+        fn signature(&self) -> nu_protocol::Signature {
+            #signature
+        }
+
+        fn examples(&self) -> Vec<nu_protocol::Example> {
+            #examples
+        }
+    }
+}
+
+/// Structs of the output given with `#[command(output = Name)]`, and the conversion of the value the `run` function
+/// returns. The declaration must have a single output type, a record or a table.
+fn output_structs(options: &Options, sig: &Signature, vis: &Visibility, kind: Kind) -> Result<(Vec<TokenStream>, Option<TokenStream>), TokenStream> {
+    let Some(name) = &options.output else {
+        return Ok((Vec::new(), None));
+    };
+    let mut outputs = sig.input_output_types.iter().map(|(_, output)| output);
+    let output = outputs.next().filter(|first| outputs.all(|output| output == *first));
+    let mut types = RustTypes::new(vis);
+    match output.map(|output| types.output(name, output)) {
+        Some(Ok(Some(_))) => {}
+        Some(Err(e)) => return Err(quote! { compile_error!(#e); }),
+        _ => return Err(quote! { compile_error!("`output` needs the declaration to have a single output type, a record or a table"); }),
+    }
+    let convert = match kind {
+        Kind::Simple => quote! { .map(|output| nu_protocol::IntoValue::into_value(output, call.head)) },
+        Kind::Pipeline => quote! {
+            .map(|output| nu_protocol::PipelineData::Value(nu_protocol::IntoValue::into_value(output, call.head), None))
+        },
+    };
+    Ok((types.items, Some(convert)))
+}
+
+/// Body of `run`, calling the `run` function with `receiver` and `args`, or with `dispatch` the method handling the
+/// input type with `args`.
+fn run_body(
+    options: &Options,
+    source: &Source,
+    kind: Kind,
+    receiver: Option<TokenStream>,
+    args: TokenStream,
+    convert_output: Option<TokenStream>,
+) -> Result<TokenStream, TokenStream> {
+    if !options.dispatch {
+        let run = run_function(options, &source.name)?;
+        return Ok(quote! { #run(#receiver #args)#convert_output });
+    }
+    if options.run.is_some() {
+        return Err(quote! { compile_error!("`run` and `dispatch` can't be used together, `dispatch` calls the methods handling each input type"); });
+    }
+    let sig = &source.signature;
+    if sig.input_output_types.is_empty() {
+        return Err(quote! { compile_error!("`dispatch` needs the declaration to have input types"); });
+    }
+    // the handlers, in the order of the declaration, with the input types they are called for
+    let mut handlers: Vec<(Ident, Vec<&nu_protocol::Type>)> = Vec::new();
+    for (input, _) in &sig.input_output_types {
+        let name = format!("on_{}", snake_case(input.to_string().split('<').next().unwrap_or_default()));
+        let Some(handler) = rust_ident(&name) else {
+            let message = format!("`{}` can't be used as method name", name);
+            return Err(quote! { compile_error!(#message); });
+        };
+        match handlers.iter_mut().find(|(ident, _)| *ident == handler) {
+            Some((_, types)) if !types.contains(&input) => types.push(input),
+            Some(_) => {}
+            None => handlers.push((handler, vec![input])),
+        }
+    }
+    let expected = handlers
+        .iter()
+        .flat_map(|(_, types)| types.iter().map(|ty| ty.to_string()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let branches = handlers.iter().map(|(handler, types)| {
+        let condition = types
+            .iter()
+            .map(|ty| {
+                let ty_token = build_sig::type_to_token(ty);
+                let list = matches!(ty, nu_protocol::Type::List(_) | nu_protocol::Type::Table(_));
+                // an empty list, or one mixing types, is a `list<any>`, which goes to the first list handler
+                let any_list = list.then(|| quote! { || input_type == nu_protocol::Type::List(Box::new(nu_protocol::Type::Any)) });
+                // the items of a stream can't be checked without consuming it, it goes to the first list handler too
+                let stream = (kind == Kind::Pipeline && list)
+                    .then(|| quote! { || matches!(input, nu_protocol::PipelineData::ListStream(..)) });
+                quote! { input_type.is_subtype(&#ty_token) #any_list #stream }
+            })
+            .reduce(|conditions, condition| quote! { #conditions || #condition });
+        quote! {
+            if #condition {
+                return self.#handler(#args)#convert_output;
+            }
+        }
+    });
+    let input_type = input_type(kind);
+    let src_span = match kind {
+        Kind::Simple => quote! { input.span() },
+        Kind::Pipeline => quote! { input.span().unwrap_or(call.head) },
+    };
+
+    Ok(quote! {
+        let input_type: nu_protocol::Type = #input_type;
+        #(#branches)*
+        Err(nu_protocol::ShellError::OnlySupportsThisInputType {
+            exp_input_type: String::from(#expected),
+            wrong_type: input_type.to_string(),
+            dst_span: call.head,
+            src_span: #src_span,
+        }
+        .into())
+    })
+}
+
+/// Type of the input, `list<any>` for a list stream whose items are unknown.
+fn input_type(kind: Kind) -> TokenStream {
+    match kind {
+        Kind::Simple => quote! { input.get_type() },
+        Kind::Pipeline => quote! {
+            match &input {
+                nu_protocol::PipelineData::Empty => nu_protocol::Type::Nothing,
+                nu_protocol::PipelineData::Value(value, _) => value.get_type(),
+                nu_protocol::PipelineData::ListStream(..) => nu_protocol::Type::List(Box::new(nu_protocol::Type::Any)),
+                nu_protocol::PipelineData::ByteStream(stream, _) => stream.type_().into(),
+            }
+        },
+    }
+}
+
+/// With `check_output`, wraps the body of `run` returning `result`: in debug builds, the output must have one of the
+/// types declared for the input. Streams aren't checked, it would consume them.
+fn check_output(options: &Options, source: &Source, kind: Kind, body: TokenStream, result: TokenStream) -> Result<TokenStream, TokenStream> {
+    if !options.check_output {
+        return Ok(body);
+    }
+    let sig = &source.signature;
+    if sig.input_output_types.is_empty() {
+        return Err(quote! { compile_error!("`check_output` needs the declaration to have input/output types"); });
+    }
+    let input_type = input_type(kind);
+    let types = sig.input_output_types.iter().map(|(input, output)| {
+        let input = build_sig::type_to_token(input);
+        let output = build_sig::type_to_token(output);
+        quote! { (#input, #output) }
+    });
+    let output_type = match kind {
+        Kind::Simple => quote! { Some(output.get_type()) },
+        Kind::Pipeline => quote! {
+            match output {
+                nu_protocol::PipelineData::Empty => Some(nu_protocol::Type::Nothing),
+                nu_protocol::PipelineData::Value(value, _) => Some(value.get_type()),
+                _ => None,
+            }
+        },
+    };
+    let name = &source.name;
+
+    Ok(quote! {
+        let input_type: nu_protocol::Type = #input_type;
+        let output: #result = (|| { #body })();
+        if cfg!(debug_assertions) {
+            if let Ok(output) = &output {
+                // an empty list, or one mixing types like records with different columns, is a `list<any>`, which may
+                // be any declared list or table
+                let compatible = |actual: &nu_protocol::Type, declared: &nu_protocol::Type| {
+                    actual.is_subtype(declared)
+                        || (*actual == nu_protocol::Type::List(Box::new(nu_protocol::Type::Any))
+                            && matches!(declared, nu_protocol::Type::List(_) | nu_protocol::Type::Table(_)))
+                };
+                let expected = [#(#types),*]
+                    .into_iter()
+                    .filter(|(input, _)| compatible(&input_type, input))
+                    .map(|(_, output)| output)
+                    .collect::<Vec<_>>();
+                let actual: Option<nu_protocol::Type> = #output_type;
+                if let Some(actual) = actual.filter(|actual| !expected.is_empty() && !expected.iter().any(|expected| compatible(actual, expected))) {
+                    let expected = expected.iter().map(|ty| ty.to_string()).collect::<Vec<_>>().join(" or ");
+                    return Err(nu_protocol::ShellError::GenericError {
+                        error: format!("`{}` returned an output of an undeclared type", #name),
+                        msg: format!("expected {} for an input of type {}, found {}", expected, input_type, actual),
+                        span: Some(call.head),
+                        help: Some(String::from("the input/output types of the declaration don't match what the command returns")),
+                        inner: Vec::new(),
+                    }
+                    .into());
+                }
+            }
+        }
+        output
+    })
+}
+
+/// Function called by `run`: the one given in the options, or the one named after the command.
+fn run_function(options: &Options, name: &str) -> Result<TokenStream, TokenStream> {
+    if let Some(path) = &options.run {
+        return Ok(quote! { #path });
+    }
+    match rust_ident(&snake_case(name)) {
+        Some(ident) => Ok(quote! { #ident }),
+        None => {
+            let message = format!("`{}` can't be used as function name, set one with `#[command(run = path)]`", snake_case(name));
+            Err(quote! { compile_error!(#message); })
+        }
+    }
+}
+
+/// `SimplePluginCommand` when no declared input or output can be a stream, `PluginCommand` otherwise.
+fn default_kind(sig: &Signature) -> Kind {
+    use nu_protocol::Type;
+    let streamable = |ty: &Type| matches!(ty, Type::Any | Type::List(_) | Type::Table(_) | Type::Binary | Type::ListStream);
+    let simple = !sig.input_output_types.is_empty()
+        && sig.input_output_types.iter().all(|(input, output)| !streamable(input) && !streamable(output));
+    if simple { Kind::Simple } else { Kind::Pipeline }
+}
+
+/// Expressions reading the arguments from the call, in the order of the signature: the required positionals, the
+/// optional ones, the rest, then the flags. The structs of their records are named after the command and the parameter.
+fn arguments(types: &mut RustTypes, command: &Ident, source: &Source) -> Result<Vec<TokenStream>, String> {
+    let sig = &source.signature;
+    let mut param_type = |param: &str, shape: &SyntaxShape, values: Option<&[String]>| {
+        let name = format_ident!("{}{}", command, rust_types::pascal_case(param));
+        types.param(&name, param, shape, values)
+    };
+    let default = |value: &Option<nu_protocol::Value>, ty: &TokenStream| {
+        value.as_ref().map(|value| {
+            let value = build_sig::default_value_to_token(value, &source.interpolations);
+            quote! { <#ty as nu_protocol::FromValue>::from_value(#value)? }
+        })
+    };
+    let mut args = Vec::new();
+    for (i, arg) in sig.required_positional.iter().enumerate() {
+        let ty = param_type(&arg.name, &arg.shape, source.allowed.positional(&arg.name))?;
+        args.push(quote! { call.req::<#ty>(#i)? });
+    }
+    for (i, arg) in sig.optional_positional.iter().enumerate() {
+        let i = i + sig.required_positional.len();
+        let ty = param_type(&arg.name, &arg.shape, source.allowed.positional(&arg.name))?;
+        args.push(match default(&arg.default_value, &ty) {
+            Some(default) => quote! {
+                match call.opt::<#ty>(#i)? {
+                    Some(value) => value,
+                    None => #default,
+                }
+            },
+            None => quote! { call.opt::<#ty>(#i)? },
+        });
+    }
+    if let Some(rest) = sig.rest_positional.as_ref().filter(|rest| !is_default_rest(rest)) {
+        let i = sig.required_positional.len() + sig.optional_positional.len();
+        let ty = param_type(&rest.name, &rest.shape, source.allowed.positional(&rest.name))?;
+        args.push(quote! { call.rest::<#ty>(#i)? });
+    }
+    for flag in sig.named.iter().filter(|flag| flag.long != "help") {
+        let name = if flag.long.is_empty() { flag.short.map(String::from).unwrap_or_default() } else { flag.long.clone() };
+        let Some(shape) = &flag.arg else {
+            args.push(quote! { call.has_flag(#name)? });
+            continue;
+        };
+        let ty = param_type(&name, shape, source.allowed.flag(flag))?;
+        args.push(match default(&flag.default_value, &ty) {
+            Some(default) => quote! {
+                match call.get_flag::<#ty>(#name)? {
+                    Some(value) => value,
+                    None => #default,
+                }
+            },
+            None => quote! { call.get_flag::<#ty>(#name)? },
+        });
+    }
+    Ok(args)
+}
+
+/// The `...args` parameter `extern` adds when no rest parameter is declared, which the generated signature drops.
+pub fn is_default_rest(rest: &nu_protocol::PositionalArg) -> bool {
+    rest.name == "args" && rest.shape == SyntaxShape::ExternalArgument
+}
+
+/// `hello-world` or `matrix hello` as `hello_world` and `matrix_hello`.
+fn snake_case(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+/// Identifier for a name, raw if it is a keyword.
+fn rust_ident(name: &str) -> Option<Ident> {
+    syn::parse_str::<Ident>(name)
+        .or_else(|_| syn::parse_str::<Ident>(&format!("r#{}", name)))
+        .ok()
+}
\ No newline at end of file
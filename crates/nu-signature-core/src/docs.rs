@@ -0,0 +1,300 @@
+//! Documentation pages of the commands, in markdown or HTML, and the index linking them by category.
+//!
+//! The pages are built from the declarations as headings, paragraphs, code blocks and tables, rendered in either
+//! format.
+use nu_protocol::{SyntaxShape, Value};
+
+use crate::{command, interpolate, Declaration};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Html => "html",
+        }
+    }
+}
+
+enum Inline {
+    Text(String),
+    Code(String),
+    Link(String, String),
+}
+
+enum Block {
+    Heading(usize, String),
+    Paragraph(Vec<Inline>),
+    Code(String),
+    Table(Vec<&'static str>, Vec<Vec<Inline>>),
+}
+
+/// The page of each command and the index, as file names and contents. Fails when two commands, or a command and the
+/// index, would be written to the same file.
+pub fn render(declarations: &[Declaration], format: Format) -> Result<Vec<(String, String)>, String> {
+    let index_name = format!("index.{}", format.extension());
+    let mut files: Vec<(String, String)> = Vec::with_capacity(declarations.len() + 1);
+    for (i, declaration) in declarations.iter().enumerate() {
+        let name = &declaration.signature.name;
+        let file = file_name(name, format);
+        if let Some(other) = declarations[..i].iter().map(|other| &other.signature.name).find(|other| file_name(other, format) == file) {
+            return Err(format!("the pages of `{}` and `{}` would both be written to `{}`", other, name, file));
+        }
+        if file == index_name {
+            return Err(format!("the page of `{}` would overwrite the index `{}`", name, file));
+        }
+        files.push((file, page(declaration, format)));
+    }
+    files.push((index_name, index(declarations, format)));
+    Ok(files)
+}
+
+/// Name of the page of a command: `str-hello.md` for `str hello`.
+pub fn file_name(command: &str, format: Format) -> String {
+    let name = command
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '_' { c } else { '-' })
+        .collect::<String>();
+    format!("{}.{}", name, format.extension())
+}
+
+/// Page of a command: its descriptions, usage, flags, parameters, input/output types and examples.
+pub fn page(declaration: &Declaration, format: Format) -> String {
+    let sig = &declaration.signature;
+    let mut blocks = vec![Block::Heading(1, sig.name.clone())];
+    if !sig.description.is_empty() {
+        blocks.push(Block::Paragraph(vec![Inline::Text(sig.description.clone())]));
+    }
+    if !sig.extra_description.is_empty() {
+        blocks.push(Block::Paragraph(vec![Inline::Text(sig.extra_description.clone())]));
+    }
+    blocks.push(Block::Paragraph(vec![
+        Inline::Text("Category: ".to_string()),
+        Inline::Code(sig.category.to_string()),
+    ]));
+    if !sig.search_terms.is_empty() {
+        blocks.push(Block::Paragraph(vec![Inline::Text(format!("Search terms: {}", sig.search_terms.join(", ")))]));
+    }
+
+    blocks.push(Block::Heading(2, "Usage".to_string()));
+    blocks.push(Block::Code(format!("> {}", sig.call_signature().trim_end())));
+
+    let flags = sig
+        .named
+        .iter()
+        .filter(|flag| flag.long != "help")
+        .map(|flag| {
+            vec![
+                Inline::Code(if flag.long.is_empty() { String::new() } else { format!("--{}", flag.long) }),
+                Inline::Code(flag.short.map(|short| format!("-{}", short)).unwrap_or_default()),
+                Inline::Code(flag.arg.as_ref().map_or_else(|| "switch".to_string(), shape_to_string)),
+                match (&flag.default_value, flag.required) {
+                    (_, true) => Inline::Text("required".to_string()),
+                    (Some(value), _) => Inline::Code(default_to_string(value)),
+                    (None, _) => Inline::Text(String::new()),
+                },
+                Inline::Text(flag.desc.clone()),
+            ]
+        })
+        .collect::<Vec<_>>();
+    if !flags.is_empty() {
+        blocks.push(Block::Heading(2, "Flags".to_string()));
+        blocks.push(Block::Table(vec!["Flag", "Short", "Type", "Default", "Description"], flags));
+    }
+
+    let positional = |name: String, arg: &nu_protocol::PositionalArg, required: bool| {
+        vec![
+            Inline::Code(name),
+            Inline::Code(shape_to_string(&arg.shape)),
+            Inline::Text(if required { "yes" } else { "no" }.to_string()),
+            arg.default_value.as_ref().map_or_else(|| Inline::Text(String::new()), |value| Inline::Code(default_to_string(value))),
+            Inline::Text(arg.desc.clone()),
+        ]
+    };
+    let mut params = Vec::new();
+    params.extend(sig.required_positional.iter().map(|arg| positional(arg.name.clone(), arg, true)));
+    params.extend(sig.optional_positional.iter().map(|arg| positional(arg.name.clone(), arg, false)));
+    if let Some(rest) = sig.rest_positional.as_ref().filter(|rest| !command::is_default_rest(rest)) {
+        params.push(positional(format!("...{}", rest.name), rest, false));
+    }
+    if !params.is_empty() {
+        blocks.push(Block::Heading(2, "Parameters".to_string()));
+        blocks.push(Block::Table(vec!["Parameter", "Type", "Required", "Default", "Description"], params));
+    }
+
+    if !sig.input_output_types.is_empty() {
+        let types = sig
+            .input_output_types
+            .iter()
+            .map(|(input, output)| vec![Inline::Code(input.to_string()), Inline::Code(output.to_string())])
+            .collect();
+        blocks.push(Block::Heading(2, "Input/output types".to_string()));
+        blocks.push(Block::Table(vec!["Input", "Output"], types));
+    }
+
+    if !declaration.examples.is_empty() {
+        blocks.push(Block::Heading(2, "Examples".to_string()));
+        for example in &declaration.examples {
+            blocks.push(Block::Paragraph(vec![Inline::Text(example.description.clone())]));
+            blocks.push(Block::Code(format!("> {}", example.example)));
+            if let Some(result) = &example.result {
+                blocks.push(Block::Paragraph(vec![Inline::Text("Result:".to_string())]));
+                blocks.push(Block::Code(nuon(result)));
+            }
+        }
+    }
+
+    render_blocks(&sig.name, &blocks, format)
+}
+
+/// Index of the commands, grouped by category, linking to their pages.
+pub fn index(declarations: &[Declaration], format: Format) -> String {
+    let mut declarations = declarations.iter().collect::<Vec<_>>();
+    declarations.sort_by(|a, b| {
+        (a.signature.category.to_string(), &a.signature.name).cmp(&(b.signature.category.to_string(), &b.signature.name))
+    });
+    let mut blocks = vec![Block::Heading(1, "Commands".to_string())];
+    for group in declarations.chunk_by(|a, b| a.signature.category == b.signature.category) {
+        blocks.push(Block::Heading(2, group[0].signature.category.to_string()));
+        let rows = group
+            .iter()
+            .map(|declaration| {
+                let name = &declaration.signature.name;
+                vec![
+                    Inline::Link(name.clone(), file_name(name, format)),
+                    Inline::Text(declaration.signature.description.clone()),
+                ]
+            })
+            .collect();
+        blocks.push(Block::Table(vec!["Command", "Description"], rows));
+    }
+    render_blocks("Commands", &blocks, format)
+}
+
+/// Shape as written in nu, without its completer.
+pub fn shape_to_string(shape: &SyntaxShape) -> String {
+    match shape {
+        SyntaxShape::CompleterWrapper(inner, _) => shape_to_string(inner),
+        shape => shape.to_string(),
+    }
+}
+
+/// Default value as written in nu, `{NAME}` for the Rust constants given to the macros.
+pub fn default_to_string(value: &Value) -> String {
+    match interpolate::placeholder_name(value) {
+        Some(name) => format!("{{{}}}", name),
+        None => nuon(value),
+    }
+}
+
+/// Value as nuon, nushell's object notation.
+pub fn nuon(value: &Value) -> String {
+    match value {
+        Value::Bool { val, .. } => val.to_string(),
+        Value::Int { val, .. } => val.to_string(),
+        Value::Float { val, .. } => format!("{:?}", val),
+        Value::Filesize { val, .. } => format!("{}b", val),
+        Value::Duration { val, .. } => format!("{}ns", val),
+        Value::Date { val, .. } => val.to_rfc3339(),
+        Value::String { val, .. } | Value::Glob { val, .. } => format!("{:?}", val),
+        Value::List { vals, .. } => format!("[{}]", vals.iter().map(nuon).collect::<Vec<_>>().join(", ")),
+        Value::Record { val, .. } => {
+            let columns = val.iter().map(|(column, value)| format!("{}: {}", nuon_key(column), nuon(value)));
+            format!("{{{}}}", columns.collect::<Vec<_>>().join(", "))
+        }
+        Value::Binary { val, .. } => format!("0x[{}]", val.iter().map(|byte| format!("{:02x}", byte)).collect::<String>()),
+        Value::Nothing { .. } => "null".to_string(),
+        value => value.to_expanded_string("", &nu_protocol::Config::default()),
+    }
+}
+
+fn nuon_key(column: &str) -> String {
+    if !column.is_empty() && column.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+        column.to_string()
+    } else {
+        format!("{:?}", column)
+    }
+}
+
+fn render_blocks(title: &str, blocks: &[Block], format: Format) -> String {
+    match format {
+        Format::Markdown => blocks.iter().map(markdown_block).collect::<Vec<_>>().join("\n\n") + "\n",
+        Format::Html => {
+            let body = blocks.iter().map(html_block).collect::<Vec<_>>().join("\n");
+            format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+                html_escape(title),
+                body
+            )
+        }
+    }
+}
+
+fn markdown_block(block: &Block) -> String {
+    match block {
+        Block::Heading(level, text) => format!("{} {}", "#".repeat(*level), text),
+        Block::Paragraph(inlines) => inlines.iter().map(markdown_inline).collect(),
+        Block::Code(code) => format!("```nushell\n{}\n```", code),
+        Block::Table(headers, rows) => {
+            let mut table = format!("| {} |\n|{}", headers.join(" | "), " --- |".repeat(headers.len()));
+            for row in rows {
+                let cells = row.iter().map(|cell| markdown_inline(cell).replace('|', "\\|").replace('\n', "<br>"));
+                table.push_str(&format!("\n| {} |", cells.collect::<Vec<_>>().join(" | ")));
+            }
+            table
+        }
+    }
+}
+
+fn markdown_inline(inline: &Inline) -> String {
+    match inline {
+        Inline::Text(text) => text.clone(),
+        Inline::Code(code) if code.is_empty() => String::new(),
+        // a code span containing backticks is delimited by a longer run of them
+        Inline::Code(code) if code.contains('`') => format!("`` {} ``", code),
+        Inline::Code(code) => format!("`{}`", code),
+        Inline::Link(text, href) => format!("[{}]({})", text, href),
+    }
+}
+
+fn html_block(block: &Block) -> String {
+    match block {
+        Block::Heading(level, text) => format!("<h{0}>{1}</h{0}>", level, html_escape(text)),
+        Block::Paragraph(inlines) => format!("<p>{}</p>", inlines.iter().map(html_inline).collect::<String>()),
+        Block::Code(code) => format!("<pre><code>{}</code></pre>", html_escape(code)),
+        Block::Table(headers, rows) => {
+            let mut table = String::from("<table>\n<tr>");
+            for header in headers {
+                table.push_str(&format!("<th>{}</th>", header));
+            }
+            table.push_str("</tr>\n");
+            for row in rows {
+                table.push_str("<tr>");
+                for cell in row {
+                    table.push_str(&format!("<td>{}</td>", html_inline(cell)));
+                }
+                table.push_str("</tr>\n");
+            }
+            table.push_str("</table>");
+            table
+        }
+    }
+}
+
+fn html_inline(inline: &Inline) -> String {
+    match inline {
+        Inline::Text(text) => html_escape(text).replace('\n', "<br>"),
+        Inline::Code(code) if code.is_empty() => String::new(),
+        Inline::Code(code) => format!("<code>{}</code>", html_escape(code)),
+        Inline::Link(text, href) => format!("<a href=\"{}\">{}</a>", html_escape(href), html_escape(text)),
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
\ No newline at end of file
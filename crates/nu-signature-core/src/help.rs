@@ -0,0 +1,133 @@
+//! The text nushell's `help <command>` prints for a signature, without colors, rendered without running nu.
+//!
+//! It follows nushell 0.97: the descriptions, the search terms, the usage line, the flags, the parameters and the
+//! input/output types, shown in a table like the `table` command does with its default configuration.
+use std::fmt::Write;
+
+use nu_protocol::{PositionalArg, Signature, SyntaxShape};
+
+use crate::build_sig;
+
+/// The help of `sig`, normalized like the signature the generated code builds, whichever parser produced it.
+pub fn help(sig: &Signature) -> String {
+    let sig = &build_sig::generated_signature(sig.clone());
+    let mut help = String::new();
+    if !sig.description.is_empty() {
+        help.push_str(&sig.description);
+        help.push_str("\n\n");
+    }
+    if !sig.extra_description.is_empty() {
+        help.push_str(&sig.extra_description);
+        help.push_str("\n\n");
+    }
+    if !sig.search_terms.is_empty() {
+        let _ = write!(help, "Search terms: {}\n\n", sig.search_terms.join(", "));
+    }
+    help.push_str(&usage(sig));
+    help.push('\n');
+    help
+}
+
+/// The help without the descriptions: the usage line, the flags, the parameters and the input/output types.
+pub fn usage(sig: &Signature) -> String {
+    let sig = &build_sig::generated_signature(sig.clone());
+    let mut help = String::new();
+    let _ = writeln!(help, "Usage:\n  > {}", sig.call_signature());
+
+    if !sig.named.is_empty() {
+        help.push_str("\nFlags:\n");
+        for flag in &sig.named {
+            let name = match (flag.short, flag.long.is_empty()) {
+                (Some(short), true) => format!("-{}", short),
+                (Some(short), false) => format!("-{}, --{}", short, flag.long),
+                (None, _) => format!("--{}", flag.long),
+            };
+            let required = if flag.required { " (required parameter)" } else { "" };
+            let arg = flag.arg.as_ref().map(|arg| format!(" <{:?}>", arg)).unwrap_or_default();
+            let default = flag.default_value.as_ref().map(|value| format!(" (default: {})", value_to_string(value))).unwrap_or_default();
+            let _ = writeln!(help, "  {}{}{} - {}{}", name, required, arg, flag.desc, default);
+        }
+    }
+
+    if !sig.required_positional.is_empty() || !sig.optional_positional.is_empty() || sig.rest_positional.is_some() {
+        help.push_str("\nParameters:\n");
+        for arg in &sig.required_positional {
+            let _ = writeln!(help, "  {}: {}", positional(arg), arg.desc);
+        }
+        for arg in &sig.optional_positional {
+            let optional = match &arg.default_value {
+                Some(value) => format!(" (optional, default: {})", value_to_string(value)),
+                None => " (optional)".to_string(),
+            };
+            let _ = writeln!(help, "  {}: {}{}", positional(arg), arg.desc, optional);
+        }
+        if let Some(rest) = &sig.rest_positional {
+            let _ = writeln!(help, "  ...{}: {}", positional(rest), rest.desc);
+        }
+    }
+
+    if !sig.input_output_types.is_empty() {
+        let rows = sig
+            .input_output_types
+            .iter()
+            .enumerate()
+            .map(|(i, (input, output))| vec![i.to_string(), input.to_string(), output.to_string()])
+            .collect::<Vec<_>>();
+        help.push_str("\nInput/output types:\n");
+        for line in table(&["#", "input", "output"], &rows).lines() {
+            let _ = writeln!(help, "  {}", line);
+        }
+    }
+    help
+}
+
+/// `name <shape>`, or `"keyword" + <shape>` for the keyword parameters.
+fn positional(arg: &PositionalArg) -> String {
+    match &arg.shape {
+        SyntaxShape::Keyword(keyword, shape) => {
+            format!("\"{}\" + <{}>", String::from_utf8_lossy(keyword), document_shape(shape))
+        }
+        shape => format!("{} <{}>", arg.name, document_shape(shape)),
+    }
+}
+
+/// The help shows the shape of the completed parameters, not their completer.
+fn document_shape(shape: &SyntaxShape) -> &SyntaxShape {
+    match shape {
+        SyntaxShape::CompleterWrapper(inner, _) => inner,
+        shape => shape,
+    }
+}
+
+fn value_to_string(value: &nu_protocol::Value) -> String {
+    value.to_parsable_string(", ", &nu_protocol::Config::default())
+}
+
+/// Table with the default `rounded` mode: centered headers, the index column then the values left aligned.
+fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let widths = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| rows.iter().map(|row| row[i].chars().count()).fold(header.chars().count(), usize::max))
+        .collect::<Vec<_>>();
+    let border = |left: &str, middle: &str, right: &str| {
+        let lines = widths.iter().map(|width| "─".repeat(width + 2)).collect::<Vec<_>>();
+        format!("{}{}{}\n", left, lines.join(middle), right)
+    };
+    let mut table = border("╭", "┬", "╮");
+    let cells = headers.iter().zip(&widths).map(|(header, width)| {
+        let space = width - header.chars().count();
+        format!(" {}{}{} ", " ".repeat(space / 2), header, " ".repeat(space - space / 2))
+    });
+    let _ = writeln!(table, "│{}│", cells.collect::<Vec<_>>().join("│"));
+    table.push_str(&border("├", "┼", "┤"));
+    for row in rows {
+        let cells = row.iter().zip(&widths).enumerate().map(|(i, (cell, width))| {
+            let space = " ".repeat(width - cell.chars().count());
+            if i == 0 { format!(" {}{} ", space, cell) } else { format!(" {}{} ", cell, space) }
+        });
+        let _ = writeln!(table, "│{}│", cells.collect::<Vec<_>>().join("│"));
+    }
+    table.push_str(&border("╰", "┴", "╯"));
+    table
+}
\ No newline at end of file
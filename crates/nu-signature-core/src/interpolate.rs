@@ -0,0 +1,155 @@
+//! Rust expressions spliced into the nu source with `{NAME}`, only for the names given after the literal:
+//! `signature!(r#"extern hello [--port: int = {PORT}]"#, PORT = DEFAULT_PORT)`.
+//!
+//! In the code, `{NAME}` becomes a field of a constant record holding a placeholder string, so the source still parses
+//! and the default value can be recognized and replaced by the expression. nu types a constant by its value, but a
+//! field access as `any`, which any parameter type accepts. In the comments, `{NAME}` is kept as is, and the
+//! descriptions containing it are generated with `format!`.
+use nu_protocol::Value;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse::{Parse, ParseStream}, punctuated::Punctuated, Expr, Ident, Token};
+
+const PLACEHOLDER_PREFIX: &str = "__nu_signature_";
+const PLACEHOLDERS: &str = "__nu_signature";
+
+struct Interpolation {
+    name: Ident,
+    expr: Expr,
+}
+
+impl Parse for Interpolation {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        let expr = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            input.parse()?
+        } else {
+            syn::parse_quote! { #name }
+        };
+        Ok(Self { name, expr })
+    }
+}
+
+#[derive(Default)]
+pub struct Interpolations(Vec<(String, Expr)>);
+
+impl Interpolations {
+    /// Parses the comma separated list following the literal: `NAME` or `NAME = expression`.
+    pub fn parse(tokens: TokenStream) -> syn::Result<Self> {
+        use syn::parse::Parser;
+        let list = Punctuated::<Interpolation, Token![,]>::parse_terminated.parse2(tokens)?;
+        Ok(Self(list.into_iter().map(|i| (i.name.to_string(), i.expr)).collect()))
+    }
+
+    fn expr(&self, name: &str) -> Option<&Expr> {
+        self.0.iter().find(|(n, _)| n == name).map(|(_, expr)| expr)
+    }
+
+    /// Finds the name of the `{NAME}` placeholder starting at the beginning of `text`.
+    fn placeholder<'a>(&self, text: &'a str) -> Option<&'a str> {
+        let end = text.find('}')?;
+        let name = text.strip_prefix('{')?.get(..end - 1)?;
+        self.expr(name).is_some().then_some(name)
+    }
+
+    /// Replaces the placeholders in the nu code, outside of strings and comments.
+    pub fn rewrite_source(&self, source: &str) -> String {
+        if self.0.is_empty() {
+            return source.to_string();
+        }
+        let mut res = String::with_capacity(source.len());
+        let fields = self.0.iter().map(|(name, _)| format!("{0}: \"{1}{0}\"", name, PLACEHOLDER_PREFIX)).collect::<Vec<_>>();
+        // on the first line of the declaration, so that the parse errors keep their line
+        res.push_str(&format!("const {} = {{{}}}; ", PLACEHOLDERS, fields.join(", ")));
+        let mut chars = source.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '#' => {
+                    let end = source[i..].find('\n').map_or(source.len(), |e| i + e);
+                    res.push_str(&source[i..end]);
+                    while chars.next_if(|(j, _)| *j < end).is_some() {}
+                }
+                '"' | '\'' | '`' => {
+                    res.push(c);
+                    let mut escaped = false;
+                    for (_, s) in chars.by_ref() {
+                        res.push(s);
+                        match s {
+                            '\\' if c == '"' && !escaped => escaped = true,
+                            s if s == c && !escaped => break,
+                            _ => escaped = false,
+                        }
+                    }
+                }
+                '{' => match self.placeholder(&source[i..]) {
+                    Some(name) => {
+                        res.push_str(&format!("${}.{}", PLACEHOLDERS, name));
+                        while chars.next_if(|(j, _)| *j <= i + name.len() + 1).is_some() {}
+                    }
+                    None => res.push(c),
+                },
+                c => res.push(c),
+            }
+        }
+        res
+    }
+
+    /// Expression given for a default value which was a placeholder.
+    pub fn value_to_token(&self, value: &Value) -> Option<TokenStream> {
+        let expr = self.expr(placeholder_name(value)?)?;
+        Some(quote! { nu_protocol::IntoValue::into_value(#expr, nu_protocol::Span::unknown()) })
+    }
+
+    fn contains_placeholder(&self, text: &str) -> bool {
+        text.match_indices('{').any(|(i, _)| self.placeholder(&text[i..]).is_some())
+    }
+
+    /// `&'static str` for a description, formatted once if it contains placeholders.
+    pub fn static_str_to_token(&self, text: &str) -> TokenStream {
+        if !self.contains_placeholder(text) {
+            return quote! { #text };
+        }
+        let string = self.string_to_token(text);
+        quote! {{
+            static TEXT: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+            TEXT.get_or_init(|| #string).as_str()
+        }}
+    }
+
+    /// String literal for a description, or a `format!` building it if it contains placeholders.
+    pub fn string_to_token(&self, text: &str) -> TokenStream {
+        let mut format = String::with_capacity(text.len());
+        let mut args = Vec::new();
+        let mut pos = 0;
+        while let Some(c) = text[pos..].chars().next() {
+            match c {
+                '{' => match self.placeholder(&text[pos..]) {
+                    Some(name) => {
+                        format.push_str("{}");
+                        args.extend(self.expr(name));
+                        pos += name.len() + 2;
+                        continue;
+                    }
+                    None => format.push_str("{{"),
+                },
+                '}' => format.push_str("}}"),
+                c => format.push(c),
+            }
+            pos += c.len_utf8();
+        }
+        if args.is_empty() {
+            quote! { #text }
+        } else {
+            quote! { format!(#format, #(#args),*) }
+        }
+    }
+}
+
+/// Name of the Rust expression a default value stands for, when it is a placeholder.
+pub fn placeholder_name(value: &Value) -> Option<&str> {
+    match value {
+        Value::String { val, .. } => val.strip_prefix(PLACEHOLDER_PREFIX),
+        _ => None,
+    }
+}
@@ -0,0 +1,126 @@
+//! JSON export of the declarations, for the tools working on the commands, like documentation sites or compatibility
+//! checkers.
+//!
+//! The document holds the version of its schema. Within a version, fields can be added but never removed or changed,
+//! the version is increased otherwise. The shapes and types are written like in nu (`list<string>`), and the default
+//! values as nuon.
+use serde::{Deserialize, Serialize};
+
+use crate::{command, docs, Declaration};
+
+/// Version of the schema of the exported document.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Export {
+    pub version: u32,
+    pub commands: Vec<Command>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Command {
+    pub name: String,
+    pub description: String,
+    pub extra_description: String,
+    pub category: String,
+    pub search_terms: Vec<String>,
+    pub required_positional: Vec<Positional>,
+    pub optional_positional: Vec<Positional>,
+    pub rest_positional: Option<Positional>,
+    /// The flags, without `--help` which every command has.
+    pub flags: Vec<Flag>,
+    pub input_output_types: Vec<InputOutput>,
+    pub examples: Vec<Example>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Positional {
+    pub name: String,
+    pub shape: String,
+    pub description: String,
+    /// As nuon, or `{NAME}` for the Rust constants given to the macros.
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Flag {
+    /// Empty for the flags which only have a short name.
+    pub long: String,
+    pub short: Option<char>,
+    /// `None` for the switches.
+    pub shape: Option<String>,
+    pub required: bool,
+    pub description: String,
+    /// As nuon, or `{NAME}` for the Rust constants given to the macros.
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputOutput {
+    pub input: String,
+    pub output: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Example {
+    pub description: String,
+    pub example: String,
+    /// As nuon.
+    pub result: Option<String>,
+}
+
+pub fn export(declarations: &[Declaration]) -> Export {
+    Export { version: SCHEMA_VERSION, commands: declarations.iter().map(export_command).collect() }
+}
+
+/// The exported document, as indented JSON.
+pub fn to_json(declarations: &[Declaration]) -> String {
+    serde_json::to_string_pretty(&export(declarations)).expect("the export only holds strings, lists and objects")
+}
+
+fn export_command(declaration: &Declaration) -> Command {
+    let sig = &declaration.signature;
+    let positional = |arg: &nu_protocol::PositionalArg| Positional {
+        name: arg.name.clone(),
+        shape: docs::shape_to_string(&arg.shape),
+        description: arg.desc.clone(),
+        default: arg.default_value.as_ref().map(docs::default_to_string),
+    };
+    Command {
+        name: sig.name.clone(),
+        description: sig.description.clone(),
+        extra_description: sig.extra_description.clone(),
+        category: sig.category.to_string(),
+        search_terms: sig.search_terms.clone(),
+        required_positional: sig.required_positional.iter().map(positional).collect(),
+        optional_positional: sig.optional_positional.iter().map(positional).collect(),
+        rest_positional: sig.rest_positional.as_ref().filter(|rest| !command::is_default_rest(rest)).map(positional),
+        flags: sig
+            .named
+            .iter()
+            .filter(|flag| flag.long != "help")
+            .map(|flag| Flag {
+                long: flag.long.clone(),
+                short: flag.short,
+                shape: flag.arg.as_ref().map(docs::shape_to_string),
+                required: flag.required,
+                description: flag.desc.clone(),
+                default: flag.default_value.as_ref().map(docs::default_to_string),
+            })
+            .collect(),
+        input_output_types: sig
+            .input_output_types
+            .iter()
+            .map(|(input, output)| InputOutput { input: input.to_string(), output: output.to_string() })
+            .collect(),
+        examples: declaration
+            .examples
+            .iter()
+            .map(|example| Example {
+                description: example.description.clone(),
+                example: example.example.clone(),
+                result: example.result.as_ref().map(docs::nuon),
+            })
+            .collect(),
+    }
+}
\ No newline at end of file
@@ -1,62 +1,276 @@
-mod parse;
+#[cfg(feature = "nu-parser")]
+pub mod parse;
+pub mod builtin_parse;
+mod allowed;
+mod attributes;
 mod build_sig;
-use proc_macro2::TokenTree;
-use quote::quote;
+mod command;
+pub mod docs;
+pub mod help;
+#[cfg(feature = "serde")]
+pub mod json;
+mod interpolate;
+mod rust_types;
+mod scan;
+#[cfg(feature = "serde")]
+pub mod schema;
+
+#[cfg(not(all(feature = "nu-parser", not(feature = "builtin-parser"))))]
+use builtin_parse::{extract_declaration, parse_value};
+#[cfg(all(feature = "nu-parser", not(feature = "builtin-parser")))]
+use parse::{extract_declaration, parse_value};
+
+pub use allowed::AllowedValues;
+use attributes::{AttributeError, Attributes};
+pub use attributes::Example;
+use interpolate::Interpolations;
+use nu_protocol::{Signature, SyntaxShape};
+use proc_macro2::{Literal, TokenTree};
+use quote::{quote, quote_spanned};
+
+/// A command declared in nu, with what its documentation shows.
+pub struct Declaration {
+    /// Signature of the command, with its name, descriptions, category and search terms.
+    pub signature: Signature,
+    pub examples: Vec<Example>,
+    /// Values accepted by the string parameters declared with a list of values, like `string@[json yaml]`.
+    pub allowed_values: AllowedValues,
+}
+
+/// Everything parsed from the input of the macros.
+struct Source {
+    attributes: Attributes,
+    name: String,
+    signature: Signature,
+    interpolations: Interpolations,
+    allowed: AllowedValues,
+}
 
 pub fn make_signature(item: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
-    // let str_item = item.to_string();
+    let source = match parse_source(item) {
+        Ok(parsed) => parsed,
+        Err(e) => return e,
+    };
+
+    build_sig::build_signature(&source.name, source.signature, &source.interpolations)
+}
+
+pub fn make_examples(item: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    let source = match parse_source(item) {
+        Ok(parsed) => parsed,
+        Err(e) => return e,
+    };
+
+    build_sig::build_examples(&source.attributes.examples)
+}
+
+pub fn make_plugin_command(item: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    let item = match syn::parse2::<command::CommandItem>(item) {
+        Ok(item) => item,
+        Err(e) => return e.to_compile_error(),
+    };
+    let source = match parse_source(item.source.clone()) {
+        Ok(parsed) => parsed,
+        Err(e) => return e,
+    };
+
+    command::plugin_command(&item, &source)
+}
+
+pub fn make_engine_command(attr: proc_macro2::TokenStream, item: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    let item = match syn::parse2::<syn::DeriveInput>(item) {
+        Ok(item @ syn::DeriveInput { data: syn::Data::Struct(_), .. }) => item,
+        Ok(item) => return syn::Error::new_spanned(item.ident, "engine_command expects a struct").to_compile_error(),
+        Err(e) => return e.to_compile_error(),
+    };
+    let source = match parse_source(attr) {
+        Ok(parsed) => parsed,
+        Err(e) => return e,
+    };
+
+    command::engine_command(item, &source)
+}
+
+/// Signature parsed from the nu source, the one the code generated by [`make_signature`] must build.
+#[doc(hidden)]
+pub fn source_signature(source: &str) -> Result<Signature, String> {
+    parse_declaration("", source).map(|declaration| declaration.signature)
+}
+
+/// `sig` without what depends on the engine state which parsed it, the variable ids and the completer ids, so that the
+/// signatures of different parsers or of the generated code can be compared.
+#[doc(hidden)]
+pub fn comparable_signature(mut sig: Signature) -> Signature {
+    fn comparable_shape(shape: &mut SyntaxShape) {
+        match shape {
+            SyntaxShape::CompleterWrapper(inner, decl_id) => {
+                *decl_id = Default::default();
+                comparable_shape(inner);
+            }
+            SyntaxShape::List(inner) | SyntaxShape::Keyword(_, inner) => comparable_shape(inner),
+            SyntaxShape::Closure(Some(shapes)) | SyntaxShape::OneOf(shapes) => shapes.iter_mut().for_each(comparable_shape),
+            SyntaxShape::Record(entries) | SyntaxShape::Table(entries) => {
+                entries.iter_mut().for_each(|(_, shape)| comparable_shape(shape))
+            }
+            _ => {}
+        }
+    }
+    let positionals = sig.required_positional.iter_mut().chain(&mut sig.optional_positional).chain(&mut sig.rest_positional);
+    for arg in positionals {
+        arg.var_id = None;
+        comparable_shape(&mut arg.shape);
+    }
+    for flag in &mut sig.named {
+        flag.var_id = None;
+        flag.arg.iter_mut().for_each(comparable_shape);
+    }
+    sig
+}
+
+/// Declarations of a nu file: its `extern`s and modules. The `def` and `const` statements are helpers of the
+/// declarations following them, like completers.
+pub fn nu_declarations(source: &str) -> Result<Vec<Declaration>, String> {
+    scan::nu_declarations(source)
+}
+
+/// Declarations given to the macros in a Rust source file, by `signature!`, `plugin_command!` and `#[engine_command]`.
+pub fn rust_declarations(source: &str) -> Result<Vec<Declaration>, String> {
+    scan::rust_declarations(source)
+}
+
+/// Parses the attributes and the declaration of `source`, after the helper statements of `helpers`.
+fn parse_declaration(helpers: &str, source: &str) -> Result<Declaration, String> {
+    let (attributes, declaration) = attributes::extract_attributes(source).map_err(|e| e.message)?;
+    let (allowed_values, declaration) = AllowedValues::extract(&format!("{}{}", helpers, declaration))?;
+    let (_, mut signature) = extract_declaration(declaration.as_bytes())?;
+    attributes.apply(&mut signature);
+    Ok(Declaration { signature: build_sig::generated_signature(signature), examples: attributes.examples, allowed_values })
+}
+
+/// Reads the literal string given to the macros, followed by the optional interpolated Rust expressions, and parses 
+/// the attributes and the declaration it contains.
+fn parse_source(item: proc_macro2::TokenStream) -> Result<Source, proc_macro2::TokenStream> {
     let mut iter = item.into_iter();
     
     let Some(TokenTree::Literal(lit_item)) = iter.next() else {
-        return quote! { compile_error!("make_signature expects a literal string containing the signature") };
+        return Err(quote! { compile_error!("make_signature expects a literal string containing the signature") });
+    };
+    let interpolations = match iter.next() {
+        None => Interpolations::default(),
+        Some(TokenTree::Punct(p)) if p.as_char() == ',' => match Interpolations::parse(iter.collect()) {
+            Ok(interpolations) => interpolations,
+            Err(e) => return Err(e.to_compile_error()),
+        },
+        Some(_) => return Err(quote! { compile_error!("make_signature only expects a literal string containing the signature, optionally followed by the interpolated names") }),
     };
-    if iter.next().is_some() {
-        return quote! { compile_error!("make_signature only expects a literal string containing the signature") };
-    }
     let str_item = lit_item.to_string();
     let trimmed = match literal_to_string(&str_item) {
         Ok(s) => s,
-        Err(e) => return quote! { compile_error!(#e) }
+        Err(e) => return Err(quote! { compile_error!(#e) })
     };
-    // let test = trimmed.chars().map(|c| format!("'{}'", c)).collect::<Vec<_>>().join(", ");
-    // return quote! { compile_error!(#test) };
-    
-    let (name, sig) = match parse::extract_declaration(trimmed.as_bytes()) {
+
+    let (attributes, declaration) = match attributes::extract_attributes(&trimmed) {
+        Ok(extracted) => extracted,
+        Err(e) => return Err(attribute_error(&lit_item, &str_item, &trimmed, e)),
+    };
+    let (allowed, declaration) = match AllowedValues::extract(&declaration) {
+        Ok(extracted) => extracted,
+        Err(e) => return Err(quote! { compile_error!(#e) }),
+    };
+    let declaration = interpolations.rewrite_source(&declaration);
+    let (name, mut signature) = match extract_declaration(declaration.as_bytes()) {
         Ok(parsed) => parsed,
-        Err(e) => return quote! { compile_error!(#e) }
+        Err(e) => return Err(quote! { compile_error!(#e) })
     };
+    attributes.apply(&mut signature);
+    let signature = build_sig::generated_signature(signature);
 
-    build_sig::build_signature(&name, sig)
+    Ok(Source { attributes, name, signature, interpolations, allowed })
 }
 
-fn literal_to_string(item: &str) -> Result<String, String> {
-    if item.chars().next() == Some('r') {
-        let item_bytes = item.as_bytes();
-        let mut begin = 0;
-        while (begin < item_bytes.len()) && (item_bytes[begin] != b'"') {
-            begin += 1;
-        }
-        let (begin, end) = (begin+1, item_bytes.len() - begin);
-        Ok(unsafe { String::from_utf8_unchecked(Vec::from(&item_bytes[begin..end])) })
+/// Reports an attribute error on the attribute itself when the compiler can locate it inside the literal, on the whole
+/// literal otherwise.
+fn attribute_error(lit: &Literal, lit_str: &str, source: &str, error: AttributeError) -> proc_macro2::TokenStream {
+    let line = source[..error.range.start].matches('\n').count() + 1;
+    let message = format!("{} (line {})", error.message, line);
+    let content_start = if lit_str.starts_with('r') {
+        lit_str.find('"').map(|i| i + 1)
+    } else if !lit_str.contains('\\') {
+        Some(1)
     } else {
-        let mut res = String::with_capacity(item.len());
-        let mut escape = false;
-        for c in item.chars().skip(1).take(item.len() - 2) {
-            match (c, escape) {
-                ('\\', false) => {
-                    escape = true;
-                    continue;
-                },
-                (c, false) => res.push(c),
-                ('\\', true) => res.push('\\'),
-                ('"', true) => res.push('"'),
-                ('n', true) => res.push('\n'),
-                ('r', true) => res.push('\r'),
-                ('t', true) => res.push('\t'),
-                (_, true) => return Err(format!(r#"Invalid escape sequence: \{}"#, c)), 
+        None
+    };
+    let span = content_start
+        .and_then(|start| lit.subspan(start + error.range.start..start + error.range.end))
+        .unwrap_or_else(|| lit.span());
+    quote_spanned! { span=> compile_error!(#message) }
+}
+
+/// Decodes the literal string given to the macros, as written in the source (`"..."` or `r#"..."#`), like rustc does.
+#[doc(hidden)]
+pub fn literal_to_string(item: &str) -> Result<String, String> {
+    const NOT_A_STRING: &str = "make_signature expects a literal string containing the signature";
+    if let Some(raw) = item.strip_prefix('r') {
+        let hashes = raw.find('"').ok_or(NOT_A_STRING)?;
+        return raw.len()
+            .checked_sub(hashes + 1)
+            .and_then(|end| raw.get(hashes + 1..end))
+            .map(str::to_string)
+            .ok_or_else(|| NOT_A_STRING.to_string());
+    }
+    // the literal may end with a suffix, ignored like rustc does for strings
+    let (content, _) = item.strip_prefix('"').and_then(|s| s.rsplit_once('"')).ok_or(NOT_A_STRING)?;
+    let mut res = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {}
+            '\r' if chars.peek() == Some(&'\n') => continue,
+            c => {
+                res.push(c);
+                continue;
+            }
+        }
+        match chars.next() {
+            Some('\\') => res.push('\\'),
+            Some('"') => res.push('"'),
+            Some('\'') => res.push('\''),
+            Some('n') => res.push('\n'),
+            Some('r') => res.push('\r'),
+            Some('t') => res.push('\t'),
+            Some('0') => res.push('\0'),
+            Some('x') => {
+                let code = chars.next().zip(chars.next())
+                    .and_then(|(high, low)| Some(high.to_digit(16)? * 16 + low.to_digit(16)?))
+                    .filter(|code| *code <= 0x7F)
+                    .ok_or("Invalid escape sequence: \\x must be followed by an ASCII code, from 00 to 7F")?;
+                res.push(char::from(code as u8));
+            }
+            Some('u') => {
+                let mut code = String::new();
+                if chars.next() == Some('{') {
+                    for c in chars.by_ref() {
+                        match c {
+                            '}' => break,
+                            '_' => {}
+                            c => code.push(c),
+                        }
+                    }
+                }
+                let c = u32::from_str_radix(&code, 16)
+                    .ok()
+                    .filter(|_| code.len() <= 6)
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!(r#"Invalid escape sequence: \u{{{}}}"#, code))?;
+                res.push(c);
+            }
+            // line continuation, the new line and the following whitespace are skipped
+            Some('\n') => {
+                while chars.next_if(|c| matches!(c, ' ' | '\t' | '\n' | '\r')).is_some() {}
             }
-            escape = false;
+            Some(c) => return Err(format!(r#"Invalid escape sequence: \{}"#, c)),
+            None => return Err(NOT_A_STRING.to_string()),
         }
-        Ok(res)
     }
+    Ok(res)
 }
\ No newline at end of file
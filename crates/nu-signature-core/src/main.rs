@@ -1,92 +1,153 @@
-use nu_protocol::{ast::Expr, Signature};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-mod keyword {
-    use engine::CommandType;
-    use nu_protocol::*;
-    use nu_protocol::{
-        engine::{
-            Command, EngineState, Stack, Call
-        }, 
-        Signature, SyntaxShape
-    };
-    #[derive(Clone)]
-    pub struct Extern;
+use nu_signature_core::{docs, Declaration};
 
-    impl Command for Extern {
-        fn name(&self) -> &str {
-            "extern"
-        }
+const USAGE: &str = "\
+Usage: nu-signature-core <command> [options] <path>...
 
-        fn description(&self) -> &str {
-            "Mock def command."
-        }
+The paths are nu files, Rust files using the macros, or directories containing them.
+
+Commands:
+  docs    Writes the documentation page of each command, and their index
+          --html            HTML pages instead of markdown
+          --output <dir>    directory of the pages, `docs` by default
+  json    Prints the signatures as JSON (with the `serde` feature)
+          --output <file>   file to write instead
+  schema  Prints the JSON Schema of the arguments of each command, by name (with the `serde` feature)
+          --output <file>   file to write instead";
 
-        fn signature(&self) -> nu_protocol::Signature {
-            Signature::build("extern")
-                .input_output_types(vec![(Type::Nothing, Type::Nothing)])
-                .required("def_name", SyntaxShape::String, "Definition name.")
-                .required("params", SyntaxShape::Signature, "Parameters.")
-                .category(Category::Core)
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let res = match args.split_first() {
+        Some((command, args)) if command == "docs" => write_docs(args),
+        #[cfg(feature = "serde")]
+        Some((command, args)) if command == "json" => write_json(args),
+        #[cfg(feature = "serde")]
+        Some((command, args)) if command == "schema" => write_schemas(args),
+        Some((command, _)) if command == "-h" || command == "--help" => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
         }
-        
-        fn command_type(&self) -> CommandType {
-            CommandType::Keyword
+        _ => Err(USAGE.to_string()),
+    };
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
         }
+    }
+}
 
-        fn run(
-            &self,
-            _engine_state: &EngineState,
-            _stack: &mut Stack,
-            _call: &Call,
-            _input: PipelineData,
-        ) -> Result<PipelineData, ShellError> {
-            Ok(PipelineData::empty())
+fn write_docs(args: &[String]) -> Result<(), String> {
+    let mut format = docs::Format::Markdown;
+    let mut output = PathBuf::from("docs");
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--html" => format = docs::Format::Html,
+            "--output" => output = args.next().ok_or("`--output` expects a directory")?.into(),
+            flag if flag.starts_with("--") => return Err(format!("unknown flag `{}`\n\n{}", flag, USAGE)),
+            path => paths.push(PathBuf::from(path)),
         }
     }
+    let declarations = declarations(&paths)?;
+    std::fs::create_dir_all(&output).map_err(|e| format!("can't create `{}`: {}", output.display(), e))?;
+    for (name, content) in docs::render(&declarations, format)? {
+        let path = output.join(name);
+        std::fs::write(&path, content).map_err(|e| format!("can't write `{}`: {}", path.display(), e))?;
+    }
+    Ok(())
 }
 
-fn extract_declaration(content: &str) -> Result<(String, Signature), String> { 
-    let engine = nu_protocol::engine::EngineState::new();
-    let mut working_set = nu_protocol::engine::StateWorkingSet::new(&engine);
-    working_set.add_decl(Box::new(keyword::Extern));
-    let ext_call = nu_parser::parse(&mut working_set, None, content.as_bytes(), false);
-    if !working_set.parse_errors.is_empty() {
-        println!("Parse errors: {:#?}", working_set.parse_errors);
-        for err in &working_set.parse_errors {
-            match err {
-                nu_protocol::ParseError::UnknownType(span) => println!("Unknown type: '{}'", std::str::from_utf8(working_set.get_span_contents(*span)).unwrap()),
-                e => println!("{:#?}", e),
-            }
+#[cfg(feature = "serde")]
+fn write_json(args: &[String]) -> Result<(), String> {
+    let (output, paths) = json_args(args)?;
+    print_json(output, nu_signature_core::json::to_json(&declarations(&paths)?))
+}
+
+#[cfg(feature = "serde")]
+fn write_schemas(args: &[String]) -> Result<(), String> {
+    let (output, paths) = json_args(args)?;
+    let schemas = declarations(&paths)?
+        .iter()
+        .map(|declaration| {
+            let schema = nu_signature_core::schema::arguments_schema(declaration)?;
+            Ok((declaration.signature.name.clone(), schema))
+        })
+        .collect::<Result<serde_json::Map<_, _>, String>>()?;
+    let json = serde_json::to_string_pretty(&schemas).expect("the schemas only hold JSON values");
+    print_json(output, json)
+}
+
+/// The `--output` file and the paths of the commands printing JSON.
+#[cfg(feature = "serde")]
+fn json_args(args: &[String]) -> Result<(Option<PathBuf>, Vec<PathBuf>), String> {
+    let mut output = None;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => output = Some(PathBuf::from(args.next().ok_or("`--output` expects a file")?)),
+            flag if flag.starts_with("--") => return Err(format!("unknown flag `{}`\n\n{}", flag, USAGE)),
+            path => paths.push(PathBuf::from(path)),
         }
     }
-    
-    if ext_call.pipelines.len() != 1 || ext_call.pipelines[0].elements.len() != 1 {
-        return Err("only def expression expected".to_string());
-    }
-    let Expr::Call(call) = &ext_call.pipelines[0].elements[0].expr.expr else {
-        return Err("def expression expected".to_string());
-    };
-    let Expr::String(ref name) = call.positional_nth(0).ok_or_else(|| "missing function name".to_string())?.expr else {
-        return Err("missing function name".to_string());
-    };
-    let decl = working_set.get_decl(working_set.find_decl(name.as_bytes()).expect("failed to find func as decl"));
-    // println!("description: {:#?}", decl.description());
-    // println!("extra_description: {:#?}", decl.extra_description());
-    // println!("name: {:#?}", decl.name());
-    // println!("command type: {:#?}", decl.command_type());
+    Ok((output, paths))
+}
 
-    
-    Ok((name.clone(), decl.signature()))
+#[cfg(feature = "serde")]
+fn print_json(output: Option<PathBuf>, json: String) -> Result<(), String> {
+    match output {
+        Some(path) => std::fs::write(&path, json).map_err(|e| format!("can't write `{}`: {}", path.display(), e)),
+        None => {
+            println!("{}", json);
+            Ok(())
+        }
+    }
 }
 
+/// Declarations of the nu and Rust files of the paths, in the order they are found.
+fn declarations(paths: &[PathBuf]) -> Result<Vec<Declaration>, String> {
+    if paths.is_empty() {
+        return Err(format!("no path given\n\n{}", USAGE));
+    }
+    let mut files = Vec::new();
+    for path in paths {
+        source_files(path, &mut files)?;
+    }
+    let mut declarations = Vec::new();
+    for file in files {
+        let source = std::fs::read_to_string(&file).map_err(|e| format!("can't read `{}`: {}", file.display(), e))?;
+        let found = match file.extension().and_then(|ext| ext.to_str()) {
+            Some("nu") => nu_signature_core::nu_declarations(&source),
+            _ => nu_signature_core::rust_declarations(&source),
+        };
+        declarations.extend(found.map_err(|e| format!("{}: {}", file.display(), e))?);
+    }
+    Ok(declarations)
+}
 
-fn main() {
-    let test = r#"
-    # description hello world
-    extern hello_world [--test1 test2:int]: nothing -> string;"#;
-    // let (tokens, errors) = nu_parser::lex(test.as_bytes(), 0, &[], &[], false);
-    // println!("tokens: {tokens:?}, errors: {errors:?}");
-    let (name, signature) = extract_declaration(test).unwrap();
-    // assert_eq!(, Expr::Nothing);
-    print!("func name: {}\nsignature: {:#?}", name, signature);
+/// Collects the `.nu` and `.rs` files of `path`, skipping the hidden directories and the build outputs.
+fn source_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let entries = std::fs::read_dir(path).map_err(|e| format!("can't read `{}`: {}", path.display(), e))?;
+    let mut entries = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect::<Vec<_>>();
+    entries.sort();
+    for entry in entries {
+        let name = entry.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        if entry.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                source_files(&entry, files)?;
+            }
+        } else if name.ends_with(".nu") || name.ends_with(".rs") {
+            files.push(entry);
+        }
+    }
+    Ok(())
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use core::str;
+use std::sync::OnceLock;
 
 use ast::Expr;
 use engine::CommandType;
@@ -6,30 +6,88 @@ use nu_protocol::*;
 use nu_protocol::{
     engine::{
         Command, EngineState, Stack, Call
-    }, 
+    },
     Signature, SyntaxShape
 };
 
+/// Mock of a nu parser keyword. The parser only needs its signature to parse the declaration.
 #[derive(Clone)]
-pub struct Extern;
+pub struct Keyword {
+    signature: Signature,
+    description: &'static str,
+}
+
+impl Keyword {
+    fn new(signature: Signature, description: &'static str) -> Self {
+        Self {
+            signature: signature
+                .input_output_types(vec![(Type::Nothing, Type::Nothing)])
+                .category(Category::Core),
+            description,
+        }
+    }
+
+    fn extern_(name: &str) -> Self {
+        Self::new(
+            Signature::build(name)
+                .required("def_name", SyntaxShape::String, "Definition name.")
+                .required("params", SyntaxShape::Signature, "Parameters."),
+            "Declare an external function.",
+        )
+    }
+
+    fn def(name: &str) -> Self {
+        Self::new(
+            Signature::build(name)
+                .required("def_name", SyntaxShape::String, "Command name.")
+                .required("params", SyntaxShape::Signature, "Parameters.")
+                .required("block", SyntaxShape::Closure(None), "Body of the definition.")
+                .switch("env", "Keep the environment defined inside the command.", None)
+                .switch("wrapped", "Treat unknown flags and arguments as strings.", None),
+            "Define a custom command.",
+        )
+    }
+
+    fn module(name: &str) -> Self {
+        Self::new(
+            Signature::build(name)
+                .required("module", SyntaxShape::String, "Module name.")
+                .required("block", SyntaxShape::Block, "Body of the module."),
+            "Define a custom module.",
+        )
+    }
+
+    fn const_() -> Self {
+        Self::new(
+            Signature::build("const")
+                .required("const_name", SyntaxShape::VarWithOptType, "Constant name.")
+                .required(
+                    "initial_value",
+                    SyntaxShape::Keyword(b"=".to_vec(), Box::new(SyntaxShape::MathExpression)),
+                    "Equals sign followed by constant value.",
+                ),
+            "Create a parse-time constant.",
+        )
+    }
 
-impl Command for Extern {
+    fn export() -> Self {
+        Self::new(Signature::build("export"), "Export definitions from a module.")
+    }
+}
+
+impl Command for Keyword {
     fn name(&self) -> &str {
-        "extern"
+        &self.signature.name
     }
 
     fn description(&self) -> &str {
-        "Declare an external function."
+        self.description
     }
 
     fn signature(&self) -> nu_protocol::Signature {
-        Signature::build("extern")
-            .input_output_types(vec![(Type::Nothing, Type::Nothing)])
-            .required("def_name", SyntaxShape::String, "Definition name.")
-            .required("params", SyntaxShape::Signature, "Parameters.")
-            .category(Category::Core)
+        self.signature.clone()
     }
-    
+
     fn command_type(&self) -> CommandType {
         CommandType::Keyword
     }
@@ -44,25 +102,145 @@ impl Command for Extern {
         Ok(PipelineData::empty())
     }
 }
-pub fn extract_declaration(content: &[u8]) -> Result<(String, Signature), String> { 
-    let engine = nu_protocol::engine::EngineState::new();
-    let mut working_set = nu_protocol::engine::StateWorkingSet::new(&engine);
-    working_set.add_decl(Box::new(Extern));
+
+/// Engine state shared by every macro invocation, with the mock keywords already merged in.
+/// Each call to [`extract_declaration`] only builds a working set delta on top of it.
+fn base_engine() -> &'static EngineState {
+    static ENGINE: OnceLock<EngineState> = OnceLock::new();
+    ENGINE.get_or_init(new_engine)
+}
+
+/// Builds a new engine state with the mock keywords. Only the benchmark calls it directly, to compare with the shared one.
+#[doc(hidden)]
+pub fn new_engine() -> EngineState {
+    let mut engine = EngineState::new();
+    let delta = {
+        let mut working_set = nu_protocol::engine::StateWorkingSet::new(&engine);
+        working_set.add_decl(Box::new(Keyword::extern_("extern")));
+        working_set.add_decl(Box::new(Keyword::extern_("export extern")));
+        working_set.add_decl(Box::new(Keyword::def("def")));
+        working_set.add_decl(Box::new(Keyword::def("export def")));
+        working_set.add_decl(Box::new(Keyword::module("module")));
+        working_set.add_decl(Box::new(Keyword::module("export module")));
+        working_set.add_decl(Box::new(Keyword::export()));
+        working_set.add_decl(Box::new(Keyword::const_()));
+        working_set.render()
+    };
+    engine.merge_delta(delta).expect("failed to register the parser keywords");
+    engine
+}
+
+/// Spans of the `def` bodies, including the ones inside modules. They are ignored, so are the errors found inside them.
+fn body_spans(working_set: &nu_protocol::engine::StateWorkingSet, block: &ast::Block, spans: &mut Vec<Span>) {
+    let calls = block.pipelines
+        .iter()
+        .flat_map(|pipeline| pipeline.elements.iter())
+        .filter_map(|element| match &element.expr.expr {
+            Expr::Call(call) => Some(call),
+            _ => None,
+        });
+    for call in calls {
+        match working_set.get_decl(call.decl_id).name() {
+            "def" | "export def" => spans.extend(call.positional_nth(2).map(|body| body.span)),
+            "module" | "export module" => {
+                if let Some(Expr::Block(block_id)) = call.positional_nth(1).map(|block| &block.expr) {
+                    body_spans(working_set, working_set.get_block(*block_id), spans);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn extract_declaration(content: &[u8]) -> Result<(String, Signature), String> {
+    extract_declaration_in(base_engine(), content)
+}
+
+/// [`extract_declaration`] on top of the given engine state instead of the shared one.
+#[doc(hidden)]
+pub fn extract_declaration_in(engine: &EngineState, content: &[u8]) -> Result<(String, Signature), String> {
+    let mut working_set = nu_protocol::engine::StateWorkingSet::new(engine);
     let ext_call = nu_parser::parse(&mut working_set, None, content, false);
-    if !working_set.parse_errors.is_empty() {
-        return Err(format!("Error while parsing the expression: {:#?}\nContent: {}", working_set.parse_errors, str::from_utf8(content).unwrap()));
+    let mut bodies = Vec::new();
+    body_spans(&working_set, &ext_call, &mut bodies);
+    let parse_errors = working_set.parse_errors
+        .iter()
+        .filter(|err| !bodies.iter().any(|body| body.contains_span(err.span())))
+        .collect::<Vec<_>>();
+    if !parse_errors.is_empty() {
+        return Err(format!("Error while parsing the expression: {}", describe_errors(&working_set, parse_errors)));
     }
-    
-    if ext_call.pipelines.len() != 1 || ext_call.pipelines[0].elements.len() != 1 {
-        return Err("only extern expression expected".to_string());
+
+    let is_call_to = |pipeline: &ast::Pipeline, keyword: &str| matches!(
+        pipeline.elements.as_slice(),
+        [element] if matches!(&element.expr.expr, Expr::Call(call) if working_set.get_decl(call.decl_id).name() == keyword)
+    );
+    // `const` statements are evaluated by the parser, the default values referencing them already hold their values
+    let pipelines = ext_call.pipelines
+        .iter()
+        .filter(|pipeline| !is_call_to(pipeline, "const"))
+        .collect::<Vec<_>>();
+    // the `def` and `export def` statements before the declaration are helpers, like the completers it uses
+    let Some((declaration, helpers)) = pipelines.split_last() else {
+        return Err("only extern or def expression expected".to_string());
+    };
+    if declaration.elements.len() != 1 || !helpers.iter().all(|pipeline| is_call_to(pipeline, "def") || is_call_to(pipeline, "export def")) {
+        return Err("only extern or def expression expected".to_string());
     }
-    let Expr::Call(call) = &ext_call.pipelines[0].elements[0].expr.expr else {
-        return Err(format!("extern expression expected, expr: {:?}", ext_call.pipelines));
+    let Expr::Call(call) = &declaration.elements[0].expr.expr else {
+        return Err("extern, def or module expression expected".to_string());
     };
     let Expr::String(ref name) = call.positional_nth(0).ok_or_else(|| "missing function name".to_string())?.expr else {
         return Err("missing function name".to_string());
     };
-    let decl = working_set.get_decl(working_set.find_decl(name.as_bytes()).ok_or_else(|| "failed to find func as decl".to_string())?);
-    
-    Ok((name.clone(), decl.signature()))
-}
\ No newline at end of file
+    let (name, decl_id) = if working_set.get_decl(call.decl_id).name() == "module" {
+        let module_id = working_set.find_module(name.as_bytes()).ok_or_else(|| "failed to find module".to_string())?;
+        let mut decls = Vec::new();
+        module_decls(&working_set, name, module_id, &mut decls);
+        match decls.len() {
+            1 => decls.remove(0),
+            0 => return Err("the module does not export any declaration".to_string()),
+            _ => return Err("the module exports more than one declaration".to_string()),
+        }
+    } else {
+        (name.clone(), working_set.find_decl(name.as_bytes()).ok_or_else(|| "failed to find func as decl".to_string())?)
+    };
+    let mut signature = working_set.get_decl(decl_id).signature();
+    signature.name.clone_from(&name);
+
+    Ok((name, signature))
+}
+
+/// Messages of the parse errors, each followed by the code it is about.
+fn describe_errors<'a>(working_set: &nu_protocol::engine::StateWorkingSet, errors: impl IntoIterator<Item = &'a ParseError>) -> String {
+    errors
+        .into_iter()
+        .map(|err| format!("{} (`{}`)", err, String::from_utf8_lossy(working_set.get_span_contents(err.span()))))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Collects the declarations exported by a module and its submodules, with their full command names.
+fn module_decls(working_set: &nu_protocol::engine::StateWorkingSet, prefix: &str, module_id: ModuleId, decls: &mut Vec<(String, DeclId)>) {
+    let module = working_set.get_module(module_id);
+    for (name, decl_id) in &module.decls {
+        decls.push((format!("{} {}", prefix, String::from_utf8_lossy(name)), *decl_id));
+    }
+    for (name, submodule_id) in &module.submodules {
+        module_decls(working_set, &format!("{} {}", prefix, String::from_utf8_lossy(name)), *submodule_id, decls);
+    }
+}
+
+/// Parses a nu constant expression, like a default value or an example result.
+pub fn parse_value(content: &[u8]) -> Result<Value, String> {
+    let mut working_set = nu_protocol::engine::StateWorkingSet::new(base_engine());
+    let block = nu_parser::parse(&mut working_set, None, content, false);
+    if !working_set.parse_errors.is_empty() {
+        return Err(format!("Error while parsing the value: {}", describe_errors(&working_set, &working_set.parse_errors)));
+    }
+    if block.pipelines.len() != 1 || block.pipelines[0].elements.len() != 1 {
+        return Err("only one value expected".to_string());
+    }
+    nu_protocol::eval_const::eval_constant(&working_set, &block.pipelines[0].elements[0].expr)
+        .map_err(|e| format!("value is not a constant: {:?}", e))
+}
@@ -0,0 +1,308 @@
+//! Rust structs generated for the records and tables of the declaration, so the command handles typed data instead of
+//! building and reading `Value`s by hand.
+//!
+//! The struct of a record output implements `IntoValue`, with the columns and the value types the declaration gives,
+//! the struct of a record parameter implements `FromValue`. A table is a `Vec` of the struct of its rows. Nested
+//! records get their own structs, named after their parent and their column. The `any` and `nothing` columns of a
+//! parameter, which can hold `null`, are `Option`s, `None` when the column is `null` or missing. A parameter with a
+//! fixed set of allowed values is an enum.
+use nu_protocol::{SyntaxShape, Type};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::Visibility;
+
+pub struct RustTypes<'a> {
+    vis: &'a Visibility,
+    /// Structs and their implementations.
+    pub items: Vec<TokenStream>,
+}
+
+impl<'a> RustTypes<'a> {
+    pub fn new(vis: &'a Visibility) -> Self {
+        Self { vis, items: Vec::new() }
+    }
+
+    /// Rust type of a record or table output, named `name`, with the `IntoValue` structs it needs. `None` if the type is
+    /// neither a record nor a table.
+    pub fn output(&mut self, name: &Ident, ty: &Type) -> Result<Option<TokenStream>, String> {
+        match ty {
+            Type::Record(columns) => self.output_struct(name, columns).map(Some),
+            Type::Table(columns) => {
+                let row = self.output_struct(name, columns)?;
+                Ok(Some(quote! { Vec<#row> }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn output_struct(&mut self, name: &Ident, columns: &[(String, Type)]) -> Result<TokenStream, String> {
+        let vis = self.vis;
+        let mut fields = Vec::new();
+        let mut values = Vec::new();
+        for ((column, ty), field) in columns.iter().zip(field_idents(name, columns)?) {
+            let field_ty = self.output_field_type(&format_ident!("{}{}", name, pascal_case(column)), ty)?;
+            let value = into_value(ty, quote! { self.#field });
+            fields.push(quote! { pub #field: #field_ty });
+            values.push(quote! { record.push(#column, #value); });
+        }
+        let capacity = columns.len();
+        self.items.push(quote! {
+            #[derive(Debug, Clone)]
+            #vis struct #name {
+                #(#fields),*
+            }
+
+            impl nu_protocol::IntoValue for #name {
+                fn into_value(self, span: nu_protocol::Span) -> nu_protocol::Value {
+                    let mut record = nu_protocol::Record::with_capacity(#capacity);
+                    #(#values)*
+                    nu_protocol::Value::record(record, span)
+                }
+            }
+        });
+        Ok(quote! { #name })
+    }
+
+    fn output_field_type(&mut self, name: &Ident, ty: &Type) -> Result<TokenStream, String> {
+        Ok(match ty {
+            Type::Int | Type::Filesize | Type::Duration => quote! { i64 },
+            Type::Float => quote! { f64 },
+            Type::Bool => quote! { bool },
+            Type::String => quote! { String },
+            Type::Glob => quote! { nu_protocol::NuGlob },
+            Type::Binary => quote! { Vec<u8> },
+            Type::List(inner) => {
+                let inner = self.output_field_type(name, inner)?;
+                quote! { Vec<#inner> }
+            }
+            Type::Record(columns) | Type::Table(columns) if !columns.is_empty() => {
+                self.output(name, ty)?.expect("records and tables have an output type")
+            }
+            _ => quote! { nu_protocol::Value },
+        })
+    }
+
+    /// Rust type an argument is converted to. Its records are structs named `name`, implementing `FromValue`, and so is
+    /// the enum of its allowed values.
+    pub fn param(&mut self, name: &Ident, param: &str, shape: &SyntaxShape, values: Option<&[String]>) -> Result<TokenStream, String> {
+        match values {
+            Some(values) => self.param_enum(name, param, values),
+            None => self.param_type(name, param, "", shape),
+        }
+    }
+
+    fn param_enum(&mut self, name: &Ident, param: &str, values: &[String]) -> Result<TokenStream, String> {
+        let vis = self.vis;
+        let mut variants: Vec<Ident> = Vec::with_capacity(values.len());
+        for value in values {
+            let variant = variant_ident(value);
+            if let Some(i) = variants.iter().position(|other| *other == variant) {
+                return Err(format!("the allowed values `{}` and `{}` are both the variant `{}` of `{}`", values[i], value, variant, name));
+            }
+            variants.push(variant);
+        }
+        let expected = values.join(", ");
+        self.items.push(quote! {
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+            #vis enum #name {
+                #(#variants),*
+            }
+
+            impl #name {
+                /// Values accepted by the parameter, in the order of the declaration, for its completions.
+                pub const VALUES: &'static [&'static str] = &[#(#values),*];
+
+                pub fn as_str(&self) -> &'static str {
+                    match self {
+                        #(Self::#variants => #values),*
+                    }
+                }
+            }
+
+            impl std::fmt::Display for #name {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str(self.as_str())
+                }
+            }
+
+            impl nu_protocol::FromValue for #name {
+                fn from_value(value: nu_protocol::Value) -> Result<Self, nu_protocol::ShellError> {
+                    let span = value.span();
+                    match <String as nu_protocol::FromValue>::from_value(value)?.as_str() {
+                        #(#values => Ok(Self::#variants),)*
+                        other => Err(nu_protocol::ShellError::IncorrectValue {
+                            msg: format!("`{}` is not allowed for `{}`, expected one of: {}", other, #param, #expected),
+                            val_span: span,
+                            call_span: span,
+                        }),
+                    }
+                }
+            }
+        });
+        Ok(quote! { #name })
+    }
+
+    /// `field` is the path of the column in the parameter, empty for the parameter itself.
+    fn param_type(&mut self, name: &Ident, param: &str, field: &str, shape: &SyntaxShape) -> Result<TokenStream, String> {
+        Ok(match shape {
+            SyntaxShape::Int | SyntaxShape::Duration | SyntaxShape::Filesize => quote! { i64 },
+            SyntaxShape::Float => quote! { f64 },
+            SyntaxShape::Boolean => quote! { bool },
+            SyntaxShape::String | SyntaxShape::Filepath | SyntaxShape::Directory | SyntaxShape::GlobPattern => quote! { String },
+            SyntaxShape::Binary => quote! { Vec<u8> },
+            SyntaxShape::CellPath => quote! { nu_protocol::ast::CellPath },
+            SyntaxShape::Closure(_) => quote! { nu_protocol::engine::Closure },
+            SyntaxShape::List(inner) => {
+                let inner = self.param_type(name, param, field, inner)?;
+                quote! { Vec<#inner> }
+            }
+            SyntaxShape::Record(entries) if !entries.is_empty() => self.param_struct(name, param, field, entries)?,
+            SyntaxShape::Table(entries) if !entries.is_empty() => {
+                let row = self.param_struct(name, param, field, entries)?;
+                quote! { Vec<#row> }
+            }
+            SyntaxShape::CompleterWrapper(inner, _) => self.param_type(name, param, field, inner)?,
+            _ => quote! { nu_protocol::Value },
+        })
+    }
+
+    fn param_struct(&mut self, name: &Ident, param: &str, field: &str, entries: &[(String, SyntaxShape)]) -> Result<TokenStream, String> {
+        let vis = self.vis;
+        let mut fields = Vec::new();
+        let mut values = Vec::new();
+        for ((column, shape), ident) in entries.iter().zip(field_idents(name, entries)?) {
+            if matches!(shape, SyntaxShape::Any | SyntaxShape::Nothing) {
+                fields.push(quote! { pub #ident: Option<nu_protocol::Value> });
+                values.push(quote! {
+                    #ident: match record.remove(#column) {
+                        None | Some(nu_protocol::Value::Nothing { .. }) => None,
+                        value => value,
+                    }
+                });
+                continue;
+            }
+            let path = if field.is_empty() { column.clone() } else { format!("{}.{}", field, column) };
+            let help = format!("in the field `{}` of the parameter `{}`", path, param);
+            let ty = self.param_type(&format_ident!("{}{}", name, pascal_case(column)), param, &path, shape)?;
+            let convert = from_value(&ty, &shape.to_type().to_string(), &help);
+            let missing = format!("missing field `{}` of the parameter `{}`", path, param);
+            fields.push(quote! { pub #ident: #ty });
+            values.push(quote! {
+                #ident: match record.remove(#column) {
+                    Some(value) => #convert,
+                    None => return Err(nu_protocol::ShellError::CantConvert {
+                        to_type: String::from("record"),
+                        from_type: String::from("record"),
+                        span,
+                        help: Some(String::from(#missing)),
+                    }),
+                }
+            });
+        }
+        let help = if field.is_empty() {
+            format!("in the parameter `{}`", param)
+        } else {
+            format!("in the field `{}` of the parameter `{}`", field, param)
+        };
+        let record = from_value(&quote! { nu_protocol::Record }, "record", &help);
+        self.items.push(quote! {
+            #[derive(Debug, Clone)]
+            #vis struct #name {
+                #(#fields),*
+            }
+
+            impl nu_protocol::FromValue for #name {
+                fn from_value(value: nu_protocol::Value) -> Result<Self, nu_protocol::ShellError> {
+                    let span = value.span();
+                    let mut record = #record;
+                    Ok(Self {
+                        #(#values),*
+                    })
+                }
+            }
+        });
+        Ok(quote! { #name })
+    }
+}
+
+/// Converts `value` to `ty`. The errors say where the value is, unless a nested conversion already did.
+fn from_value(ty: &TokenStream, to_type: &str, help: &str) -> TokenStream {
+    quote! {{
+        let (from_type, span) = (value.get_type().to_string(), value.span());
+        <#ty as nu_protocol::FromValue>::from_value(value).map_err(|error| match error {
+            error @ nu_protocol::ShellError::CantConvert { help: Some(_), .. } => error,
+            _ => nu_protocol::ShellError::CantConvert {
+                to_type: String::from(#to_type),
+                from_type,
+                span,
+                help: Some(String::from(#help)),
+            },
+        })?
+    }}
+}
+
+/// Converts `expr`, of the Rust type generated for `ty`, to a `Value` of type `ty`.
+fn into_value(ty: &Type, expr: TokenStream) -> TokenStream {
+    match ty {
+        Type::Filesize => quote! { nu_protocol::Value::filesize(#expr, span) },
+        Type::Duration => quote! { nu_protocol::Value::duration(#expr, span) },
+        Type::Binary => quote! { nu_protocol::Value::binary(#expr, span) },
+        // `NuGlob` doesn't implement `IntoValue`
+        Type::Glob => quote! {{
+            let glob: nu_protocol::NuGlob = #expr;
+            nu_protocol::Value::glob(glob.to_string(), !glob.is_expand(), span)
+        }},
+        Type::List(inner) => {
+            let inner = into_value(inner, quote! { item });
+            quote! { nu_protocol::Value::list(#expr.into_iter().map(|item| #inner).collect(), span) }
+        }
+        _ => quote! { nu_protocol::IntoValue::into_value(#expr, span) },
+    }
+}
+
+/// Fields of the struct `name` for its columns. Two columns can't have the same field.
+fn field_idents<T>(name: &Ident, columns: &[(String, T)]) -> Result<Vec<Ident>, String> {
+    let mut fields: Vec<Ident> = Vec::with_capacity(columns.len());
+    for (column, _) in columns {
+        let field = field_ident(column);
+        if let Some(i) = fields.iter().position(|other| *other == field) {
+            return Err(format!("the columns `{}` and `{}` are both the field `{}` of `{}`", columns[i].0, column, field, name));
+        }
+        fields.push(field);
+    }
+    Ok(fields)
+}
+
+/// Field named after a column, `snake_case`, raw if it is a keyword.
+fn field_ident(column: &str) -> Ident {
+    let mut name = column
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect::<String>();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    syn::parse_str::<Ident>(&name)
+        .or_else(|_| syn::parse_str::<Ident>(&format!("r#{}", name)))
+        .unwrap_or_else(|_| format_ident!("{}_", name))
+}
+
+/// Variant named after an allowed value, `PascalCase`, suffixed with `_` if it is a keyword (`Self`).
+fn variant_ident(value: &str) -> Ident {
+    let mut name = pascal_case(value);
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, 'V');
+    }
+    syn::parse_str::<Ident>(&name).unwrap_or_else(|_| format_ident!("{}_", name))
+}
+
+/// `file-size` or `file_size` as `FileSize`.
+pub fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect()
+}
\ No newline at end of file
@@ -0,0 +1,160 @@
+//! Finds the declarations of several commands, in a nu file or in the Rust sources using the macros, for the tools
+//! working on all the commands of a plugin, like the documentation.
+use std::str::FromStr;
+
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+
+use crate::{command, Declaration};
+
+/// Declarations of a nu file, each with the comments and attributes preceding it. The helpers aren't returned.
+pub fn nu_declarations(source: &str) -> Result<Vec<Declaration>, String> {
+    let mut declarations = Vec::new();
+    let mut helpers = String::new();
+    for statement in statements(source) {
+        let code = statement
+            .lines()
+            .map(str::trim_start)
+            .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('@'))
+            .unwrap_or_default();
+        let keyword = code.strip_prefix("export ").unwrap_or(code).split_whitespace().next().unwrap_or_default();
+        match keyword {
+            "" => {}
+            "def" | "const" => helpers.push_str(statement),
+            "extern" | "module" => declarations.push(crate::parse_declaration(&helpers, statement)?),
+            _ => return Err(format!("extern, module, def or const expected, found `{}`", code)),
+        }
+    }
+    Ok(declarations)
+}
+
+/// Splits a nu file into its top-level statements, each one starting with the comments and attributes before it.
+fn statements(source: &str) -> Vec<&str> {
+    #[derive(PartialEq)]
+    enum Line {
+        Empty,
+        Comment,
+        Attribute,
+        Code,
+    }
+    let bytes = source.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut line = Line::Empty;
+    let mut pos = 0;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b' ' | b'\t' | b'\r' => {}
+            b'#' => {
+                if line == Line::Empty {
+                    line = Line::Comment;
+                }
+                pos = source[pos..].find('\n').map_or(source.len(), |i| pos + i);
+                continue;
+            }
+            b'\n' | b';' if depth == 0 => {
+                if line == Line::Code {
+                    statements.push(&source[start..pos + 1]);
+                    start = pos + 1;
+                }
+                line = Line::Empty;
+            }
+            c => {
+                if line == Line::Empty {
+                    line = if c == b'@' { Line::Attribute } else { Line::Code };
+                }
+                match c {
+                    quote @ (b'"' | b'\'' | b'`') => {
+                        pos += 1;
+                        while pos < bytes.len() && bytes[pos] != quote {
+                            if quote == b'"' && bytes[pos] == b'\\' {
+                                pos += 1;
+                            }
+                            pos += 1;
+                        }
+                    }
+                    b'[' | b'{' | b'(' => depth += 1,
+                    b']' | b'}' | b')' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+        }
+        pos += 1;
+    }
+    if start < source.len() {
+        statements.push(&source[start..]);
+    }
+    statements
+}
+
+pub fn rust_declarations(source: &str) -> Result<Vec<Declaration>, String> {
+    let tokens = TokenStream::from_str(source).map_err(|e| format!("invalid Rust source: {}", e))?;
+    let mut declarations = Vec::new();
+    scan_tokens(tokens, &mut declarations)?;
+    Ok(declarations)
+}
+
+fn scan_tokens(tokens: TokenStream, declarations: &mut Vec<Declaration>) -> Result<(), String> {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    for (i, token) in tokens.iter().enumerate() {
+        let TokenTree::Group(group) = token else {
+            continue;
+        };
+        let macro_name = match i.checked_sub(2).and_then(|start| tokens.get(start..i)) {
+            Some([TokenTree::Ident(name), TokenTree::Punct(bang)]) if bang.as_char() == '!' => Some(name.to_string()),
+            _ => None,
+        };
+        let source = match macro_name.as_deref() {
+            Some("signature") => Some(group.stream()),
+            Some("plugin_command") => {
+                let item = syn::parse2::<command::CommandItem>(group.stream()).map_err(|e| e.to_string())?;
+                Some(item.source)
+            }
+            _ if group.delimiter() == Delimiter::Bracket && is_attribute(&tokens, i) => engine_command_source(group.stream()),
+            _ => None,
+        };
+        match source {
+            Some(source) => {
+                let source = crate::parse_source(source).map_err(compile_error_message)?;
+                declarations.push(Declaration {
+                    signature: source.signature,
+                    examples: source.attributes.examples,
+                    allowed_values: source.allowed,
+                });
+            }
+            None => scan_tokens(group.stream(), declarations)?,
+        }
+    }
+    Ok(())
+}
+
+/// Whether the bracket group at `i` follows a `#`, or `#!` for the inner attributes.
+fn is_attribute(tokens: &[TokenTree], i: usize) -> bool {
+    let punct = |i: usize, c: char| matches!(tokens.get(i), Some(TokenTree::Punct(p)) if p.as_char() == c);
+    (i >= 1 && punct(i - 1, '#')) || (i >= 2 && punct(i - 1, '!') && punct(i - 2, '#'))
+}
+
+/// Source given to `#[engine_command(...)]` or `#[nu_signature::engine_command(...)]`.
+fn engine_command_source(attribute: TokenStream) -> Option<TokenStream> {
+    let tokens = attribute.into_iter().collect::<Vec<_>>();
+    match tokens.as_slice() {
+        [.., TokenTree::Ident(name), TokenTree::Group(args)]
+            if name == "engine_command" && args.delimiter() == Delimiter::Parenthesis =>
+        {
+            Some(args.stream())
+        }
+        _ => None,
+    }
+}
+
+/// Message of the `compile_error!` the macros would have expanded to.
+fn compile_error_message(error: TokenStream) -> String {
+    error
+        .into_iter()
+        .find_map(|token| match token {
+            TokenTree::Literal(lit) => crate::literal_to_string(&lit.to_string()).ok(),
+            TokenTree::Group(group) => Some(compile_error_message(group.stream())),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
\ No newline at end of file
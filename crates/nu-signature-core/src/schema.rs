@@ -0,0 +1,147 @@
+//! JSON Schema of the arguments of a command, for the tools calling commands with JSON, like form generators or agents
+//! calling tools.
+//!
+//! The arguments are an object with a property per positional and per flag, named like them. The switches are booleans,
+//! the rest positional an array, and the strings with a list of allowed values an `enum`. The shapes JSON Schema has no
+//! format for, like paths, durations and file sizes, are named in the `x-nu-shape` annotation, and the unit of the
+//! durations and file sizes is in the description.
+use nu_protocol::{Signature, Span, SyntaxShape, Value};
+use serde_json::{json, Map};
+
+use crate::{command, interpolate, Declaration};
+
+pub const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Fails when two parameters would be the same property, like a positional and a flag with the same name.
+pub fn arguments_schema(declaration: &Declaration) -> Result<serde_json::Value, String> {
+    let sig = &declaration.signature;
+    let allowed = &declaration.allowed_values;
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for (arg, is_required) in sig
+        .required_positional
+        .iter()
+        .map(|arg| (arg, true))
+        .chain(sig.optional_positional.iter().map(|arg| (arg, false)))
+    {
+        let schema = values_schema(&arg.shape, allowed.positional(&arg.name));
+        insert(&mut properties, sig, &arg.name, property(schema, &arg.desc, arg.default_value.as_ref()))?;
+        if is_required {
+            required.push(arg.name.clone());
+        }
+    }
+    if let Some(rest) = sig.rest_positional.as_ref().filter(|rest| !command::is_default_rest(rest)) {
+        let schema = json!({ "type": "array", "items": values_schema(&rest.shape, allowed.positional(&rest.name)) });
+        insert(&mut properties, sig, &rest.name, property(schema, &rest.desc, None))?;
+    }
+    for flag in sig.named.iter().filter(|flag| flag.long != "help") {
+        let name = if flag.long.is_empty() { flag.short.map(String::from).unwrap_or_default() } else { flag.long.clone() };
+        let schema = match &flag.arg {
+            Some(shape) => property(values_schema(shape, allowed.flag(flag)), &flag.desc, flag.default_value.as_ref()),
+            None => property(json!({ "type": "boolean" }), &flag.desc, Some(&Value::bool(false, Span::unknown()))),
+        };
+        insert(&mut properties, sig, &name, schema)?;
+        if flag.required {
+            required.push(name);
+        }
+    }
+
+    let mut schema = json!({
+        "$schema": SCHEMA_DIALECT,
+        "title": sig.name,
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    });
+    if !sig.description.is_empty() {
+        schema["description"] = json!(sig.description);
+    }
+    Ok(schema)
+}
+
+fn insert(properties: &mut Map<String, serde_json::Value>, sig: &Signature, name: &str, schema: serde_json::Value) -> Result<(), String> {
+    if properties.insert(name.to_string(), schema).is_some() {
+        return Err(format!("several parameters of `{}` are named `{}`, they can't be properties of the same object", sig.name, name));
+    }
+    Ok(())
+}
+
+/// Schema of the values of a parameter: the ones of its shape, restricted to its allowed values if it has some.
+fn values_schema(shape: &SyntaxShape, allowed: Option<&[String]>) -> serde_json::Value {
+    let mut schema = shape_schema(shape);
+    if let Some(values) = allowed {
+        schema["enum"] = json!(values);
+    }
+    schema
+}
+
+/// Schema of an argument, with its description, followed by the one of its shape if any, and its default value.
+fn property(mut schema: serde_json::Value, description: &str, default: Option<&Value>) -> serde_json::Value {
+    if !description.is_empty() {
+        schema["description"] = match schema["description"].as_str() {
+            Some(shape_description) => json!(format!("{} ({})", description, shape_description)),
+            None => json!(description),
+        };
+    }
+    if let Some(default) = default.and_then(value_to_json) {
+        schema["default"] = default;
+    }
+    schema
+}
+
+/// Schema of the values a shape accepts. The shapes without JSON equivalent accept any value.
+pub fn shape_schema(shape: &SyntaxShape) -> serde_json::Value {
+    match shape {
+        SyntaxShape::Int => json!({ "type": "integer" }),
+        SyntaxShape::Duration => json!({ "type": "integer", "x-nu-shape": "duration", "description": "duration in nanoseconds" }),
+        SyntaxShape::Filesize => json!({ "type": "integer", "x-nu-shape": "filesize", "description": "file size in bytes" }),
+        SyntaxShape::Float | SyntaxShape::Number => json!({ "type": "number" }),
+        SyntaxShape::Boolean => json!({ "type": "boolean" }),
+        SyntaxShape::String => json!({ "type": "string" }),
+        SyntaxShape::Filepath => json!({ "type": "string", "x-nu-shape": "path" }),
+        SyntaxShape::Directory => json!({ "type": "string", "x-nu-shape": "directory" }),
+        SyntaxShape::GlobPattern => json!({ "type": "string", "x-nu-shape": "glob" }),
+        SyntaxShape::Nothing => json!({ "type": "null" }),
+        SyntaxShape::List(inner) => json!({ "type": "array", "items": shape_schema(inner) }),
+        SyntaxShape::Record(entries) => record_schema(entries),
+        SyntaxShape::Table(entries) => json!({ "type": "array", "items": record_schema(entries) }),
+        SyntaxShape::OneOf(shapes) => json!({ "anyOf": shapes.iter().map(shape_schema).collect::<Vec<_>>() }),
+        SyntaxShape::CompleterWrapper(inner, _) => shape_schema(inner),
+        _ => json!({}),
+    }
+}
+
+/// A record accepts more columns than the declared ones, which are all required.
+fn record_schema(entries: &[(String, SyntaxShape)]) -> serde_json::Value {
+    if entries.is_empty() {
+        return json!({ "type": "object" });
+    }
+    let properties = entries
+        .iter()
+        .map(|(column, shape)| (column.clone(), shape_schema(shape)))
+        .collect::<Map<_, _>>();
+    let required = entries.iter().map(|(column, _)| column.clone()).collect::<Vec<_>>();
+    json!({ "type": "object", "properties": properties, "required": required })
+}
+
+/// Default value as JSON. The Rust constants given to the macros and the values without JSON equivalent are left out.
+fn value_to_json(value: &Value) -> Option<serde_json::Value> {
+    if interpolate::placeholder_name(value).is_some() {
+        return None;
+    }
+    Some(match value {
+        Value::Bool { val, .. } => json!(val),
+        Value::Int { val, .. } | Value::Filesize { val, .. } | Value::Duration { val, .. } => json!(val),
+        Value::Float { val, .. } => json!(val),
+        Value::String { val, .. } | Value::Glob { val, .. } => json!(val),
+        Value::Nothing { .. } => serde_json::Value::Null,
+        Value::List { vals, .. } => serde_json::Value::Array(vals.iter().map(value_to_json).collect::<Option<_>>()?),
+        Value::Record { val, .. } => serde_json::Value::Object(
+            val.iter()
+                .map(|(column, value)| Some((column.clone(), value_to_json(value)?)))
+                .collect::<Option<_>>()?,
+        ),
+        _ => return None,
+    })
+}
\ No newline at end of file
@@ -0,0 +1,46 @@
+use proc_macro2::{Literal, TokenTree};
+
+const SOURCE: &str = r#"
+# Add two numbers
+@category math
+@search-terms "sum" 'plus'
+@example "Add one and two" { 1 | add 2 } --result 3
+@example "Add a list" "[1 2] | add 3"
+extern add [value: int]: [int -> int, list<int> -> list<int>]
+"#;
+
+fn literal(source: &str) -> proc_macro2::TokenStream {
+    TokenTree::Literal(Literal::string(source)).into()
+}
+
+#[test]
+fn attributes_map_onto_signature() {
+    let res = nu_signature_core::make_signature(literal(SOURCE)).to_string();
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains("category (nu_protocol :: Category :: Math)"), "{}", res);
+    assert!(res.contains(r#"search_terms (vec ! [String :: from ("sum") , String :: from ("plus")])"#), "{}", res);
+    assert!(res.contains(r#"description ("Add two numbers")"#), "{}", res);
+}
+
+#[test]
+fn default_category_is_experimental() {
+    let res = nu_signature_core::make_signature(literal("extern add []")).to_string();
+    assert!(res.contains("category (nu_protocol :: Category :: Experimental)"), "{}", res);
+}
+
+#[test]
+fn examples_from_attributes() {
+    let res = nu_signature_core::make_examples(literal(SOURCE)).to_string();
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains(r#"example : "1 | add 2""#), "{}", res);
+    assert!(res.contains(r#"description : "Add one and two""#), "{}", res);
+    assert!(res.contains(r#"example : "[1 2] | add 3""#), "{}", res);
+    assert!(res.contains("result : None"), "{}", res);
+}
+
+#[test]
+fn unknown_attribute_is_rejected() {
+    let res = nu_signature_core::make_signature(literal("@deprecated\nextern add []")).to_string();
+    assert!(res.contains("compile_error"), "{}", res);
+    assert!(res.contains("unknown attribute `@deprecated`"), "{}", res);
+}
@@ -0,0 +1,178 @@
+//! Differential test: the built-in parser must produce the same signatures as nu-parser.
+#![cfg(feature = "nu-parser")]
+
+use nu_signature_core::{builtin_parse, comparable_signature, parse};
+
+const CORPUS: &[&str] = &[
+    "extern empty []",
+    "extern 'quoted name' [];",
+    r#"
+    # description hello world
+    extern hello_world [--test1 test2:int]: nothing -> string;"#,
+    r#"
+    # Short description
+    #
+    # Extra description
+    #   indented on two lines
+    extern described [
+        pos_arg: int    # pos_arg description
+                        # on two lines
+        --switch        # switch description
+        --flag: string  # flag description
+    ]: nothing -> string;"#,
+    r#"
+    # detached comment
+
+    extern detached []"#,
+    r#"extern positionals [
+        req
+        req_typed: string,
+        opt?
+        opt_typed?: float
+        opt_default: int = 5
+        inferred = "hello"
+        ...rest: path
+    ]"#,
+    r#"extern flags [
+        --switch(-s)
+        --typed(-t): duration
+        --default: filesize = 10kb
+        --inferred = 3.5
+        --list: list<string> = [a, "b", 'c']
+        --record: record<a: int> = {a: 1}
+        -x
+    ]"#,
+    r#"extern shapes [
+        any: any, binary: binary, bool: bool, cell_path: cell-path, datetime: datetime,
+        directory: directory, duration: duration, error: error, float: float, filesize: filesize,
+        glob: glob, int: int, nothing: nothing, number: number, path: path, range: range,
+        string: string
+    ]"#,
+    r#"extern generics [
+        list: list
+        list_int: list<int>
+        nested: list<list<string>>
+        record: record
+        record_fields: record<a: int, b: string c>
+        table: table<name: string, size: filesize>
+        closure: closure
+    ]"#,
+    r#"extern io_types []: [nothing -> string, int -> list<int>
+        record<a: int> -> table<a: int>
+    ]"#,
+    r#"extern defaults [
+        --int: int = -42
+        --hex: int = 0xff
+        --float: float = 1.5e3
+        --infinity: float = -inf
+        --overflow: float = 1e999
+        --null: any = null
+        --duration: duration = 1.5sec
+        --string: string = "with \"escapes\"\n"
+        --raw: string = 'raw \n'
+        --bare: string = bare
+        --nested: list<record<a: int>> = [{a: 1}, {a: 2}]
+        --date: datetime = 2024-01-02
+        --range: range = 1..10
+        --stepped: range = 0..2..10
+        --open: range = 5..
+        --float-range: range = 1.0..3.0
+        --bytes: binary = 0x[ff 00]
+        --octal: binary = 0o[1 377]
+        --bits: binary = 0b[1 00000001]
+        --cell-path: cell-path = $.a.0?
+    ]"#,
+    r#"
+    # defined command
+    def defined [x: int, --flag: string] { $x + 1 }"#,
+    r#"def --wrapped wrapped [...rest: string]: nothing -> string { "}" # }
+    }"#,
+    r#"export extern exported [x: int]: nothing -> int"#,
+    r#"export def exported_def [x: int]: nothing -> int { $x }"#,
+    r#"extern "matrix hello" [x: int]"#,
+    r#"module matrix {
+        # private helper
+        def helper [] { }
+
+        # module scoped command
+        export extern hello [x: int]: nothing -> string
+    }"#,
+    r#"
+    const LIMIT = 100
+    const NAMES: list<string> = [a b]
+    # uses constants
+    extern constants [--limit: int = $LIMIT, names?: list<string> = $NAMES]"#,
+    r#"
+    const PORTS = {web: 80, all: [80, 443]}
+    extern cell_paths [--port: int = $PORTS.web, --secure: int = $PORTS.all.1]"#,
+    r#"
+    def formats [] { [json yaml] }
+    export def "nu-complete names" [] { [a b] }
+    # uses completers
+    extern completers [format: string@formats, --names: string@"nu-complete names"]"#,
+    r#"module polars {
+        export module into {
+            export def df [] { }
+        }
+    }"#,
+];
+
+#[test]
+fn same_signature_as_nu_parser() {
+    for source in CORPUS {
+        let (expected_name, expected) = parse::extract_declaration(source.as_bytes())
+            .unwrap_or_else(|e| panic!("nu-parser failed on:\n{}\n{}", source, e));
+        let (name, sig) = builtin_parse::extract_declaration(source.as_bytes())
+            .unwrap_or_else(|e| panic!("built-in parser failed on:\n{}\n{}", source, e));
+        assert_eq!(name, expected_name, "name mismatch for:\n{}", source);
+
+        let (expected, sig) = (comparable_signature(expected), comparable_signature(sig));
+        assert_eq!(sig.description, expected.description, "description mismatch for:\n{}", source);
+        assert_eq!(sig.extra_description, expected.extra_description, "extra description mismatch for:\n{}", source);
+        assert_eq!(sig.required_positional, expected.required_positional, "required positionals mismatch for:\n{}", source);
+        assert_eq!(sig.optional_positional, expected.optional_positional, "optional positionals mismatch for:\n{}", source);
+        assert_eq!(sig.rest_positional, expected.rest_positional, "rest positional mismatch for:\n{}", source);
+        assert_eq!(sig.named, expected.named, "flags mismatch for:\n{}", source);
+        assert_eq!(sig.input_output_types, expected.input_output_types, "input/output types mismatch for:\n{}", source);
+    }
+}
+
+#[test]
+fn same_errors_as_nu_parser() {
+    let invalid = [
+        "let not_a_declaration = 5",
+        "extern unknown_type [x: foo]",
+        "extern unsupported_type [x: signature]",
+        "extern closure_args [x: closure(int, string)]",
+        "extern oneof [x: oneof<int, string>]",
+        "extern bool_switch [--x: bool = true]",
+        "extern date_time [--x: datetime = 2024-01-02T03:04:05]",
+        "extern exclusive_range [--x: range = 1..<10]",
+        "extern octal_overflow [--x: binary = 0o[777]]",
+        "extern unclosed [x: int",
+        "extern too_many []; extern other []",
+        "extern bad_default [--x: int = \"string\"]",
+        "module empty { extern private [] }",
+        "extern unknown_const [--limit: int = $LIMIT]",
+        "const LIMIT: any = 5; extern const_type [--limit: string = $LIMIT]",
+        "const PORTS = {web: 80}; extern unknown_field [--port: int = $PORTS.ftp]",
+        "module too_many { export extern a []; export extern b [] }",
+        "extern unknown_completer [x: string@nope]",
+        "extern helper []; extern other [x: string@helper]",
+        "def formats [] { }; extern list_completer [x: list<string>@formats]",
+        "def formats [] { }; extern list_completer [x: list@formats]",
+        "def formats [] { }; extern record_completer [x: record<a: int>@formats]",
+    ];
+    for source in invalid {
+        assert!(parse::extract_declaration(source.as_bytes()).is_err(), "nu-parser accepted:\n{}", source);
+        assert!(builtin_parse::extract_declaration(source.as_bytes()).is_err(), "built-in parser accepted:\n{}", source);
+    }
+}
+
+#[test]
+fn errors_located_at_the_token() {
+    let error = builtin_parse::extract_declaration(b"extern located [\n    x: int\n    y: foo\n]").unwrap_err();
+    assert_eq!(error, "unknown type: `foo` (line 3, column 8)");
+    let error = builtin_parse::extract_declaration(b"extern located [--x: int = \"string\"]").unwrap_err();
+    assert_eq!(error, "default value of type `string` does not match the type `int` (line 1, column 28)");
+}
@@ -0,0 +1,264 @@
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+
+fn plugin_command(item: TokenStream) -> String {
+    nu_signature_core::make_plugin_command(item).to_string()
+}
+
+#[test]
+fn plugin_command_skeleton() {
+    let source = Literal::string(r#"
+        # Say hello
+        @search-terms greet
+        extern hello-world [
+            name: string
+            count?: int = 2
+            ...rest: int
+            --loud(-l)
+            --greeting: string
+        ]: nothing -> string
+    "#);
+    let res = plugin_command(quote! {
+        /// Says hello
+        pub struct HelloWorld for MyPlugin;
+        #source
+    });
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains("# [doc = r\" Says hello\"] # [doc = \"```text\\nUsage:\\n  > hello-world {flags} <name> (count) ...(rest) \\n"), "{}", res);
+    assert!(res.contains("```\"] pub struct HelloWorld ;"), "{}", res);
+    assert!(res.contains("impl nu_plugin :: SimplePluginCommand for HelloWorld { type Plugin = MyPlugin ;"), "{}", res);
+    assert!(res.contains(r#"fn name (& self) -> & str { "hello-world" }"#), "{}", res);
+    assert!(res.contains(r#"fn description (& self) -> & str { "Say hello" }"#), "{}", res);
+    assert!(res.contains(r#"fn search_terms (& self) -> Vec < & str > { vec ! ["greet"] }"#), "{}", res);
+    assert!(res.contains("hello_world (plugin , engine , call , input , call . req :: < String > (0usize) ? ,"), "{}", res);
+    assert!(res.contains("match call . opt :: < i64 > (1usize) ? { Some (value) => value , None => < i64 as nu_protocol :: FromValue > :: from_value (nu_protocol :: Value :: int (2i64"), "{}", res);
+    assert!(res.contains("call . rest :: < i64 > (2usize) ? , call . has_flag (\"loud\") ? , call . get_flag :: < String > (\"greeting\") ?)"), "{}", res);
+}
+
+#[test]
+fn run_function() {
+    let source = Literal::string("extern type [x: list<int>]");
+    let res = plugin_command(quote! { struct Type for MyPlugin; #source });
+    assert!(res.contains("r#type (plugin , engine , call , input , call . req :: < Vec < i64 > > (0usize) ?)"), "{}", res);
+
+    let res = plugin_command(quote! { #[command(run = crate::types::run)] struct Type for MyPlugin; #source });
+    assert!(res.contains("crate :: types :: run (plugin"), "{}", res);
+
+    let res = plugin_command(quote! { #[command(unknown)] struct Type for MyPlugin; #source });
+    assert!(res.contains("compile_error"), "{}", res);
+}
+
+#[test]
+fn simple_or_pipeline_command() {
+    let trait_of = |options: TokenStream, source: &str| {
+        let source = Literal::string(source);
+        let res = plugin_command(quote! { #options struct Hello for MyPlugin; #source });
+        assert!(!res.contains("compile_error"), "{}", res);
+        if res.contains("impl nu_plugin :: SimplePluginCommand for Hello") {
+            assert!(res.contains("input : & nu_protocol :: Value ,) -> Result < nu_protocol :: Value , nu_protocol :: LabeledError >"), "{}", res);
+            "simple"
+        } else {
+            assert!(res.contains("input : nu_protocol :: PipelineData ,) -> Result < nu_protocol :: PipelineData , nu_protocol :: LabeledError >"), "{}", res);
+            "pipeline"
+        }
+    };
+    assert_eq!(trait_of(quote! {}, "extern hello []: [string -> int, int -> record<a: int>]"), "simple");
+    assert_eq!(trait_of(quote! {}, "extern hello []: string -> list<string>"), "pipeline");
+    assert_eq!(trait_of(quote! {}, "extern hello []: table -> int"), "pipeline");
+    assert_eq!(trait_of(quote! {}, "extern hello []"), "pipeline");
+    assert_eq!(trait_of(quote! { #[command(simple)] }, "extern hello []: list<int> -> int"), "simple");
+    assert_eq!(trait_of(quote! { #[command(pipeline)] }, "extern hello []: int -> int"), "pipeline");
+
+    let source = Literal::string("extern hello []");
+    let res = plugin_command(quote! { #[command(simple, pipeline)] struct Hello for MyPlugin; #source });
+    assert!(res.contains("compile_error"), "{}", res);
+}
+
+#[test]
+fn engine_command() {
+    let source = Literal::string(r#"
+        # Say hello
+        extern "str hello" [name: string]: nothing -> string
+    "#);
+    let res = nu_signature_core::make_engine_command(
+        quote! { #source },
+        quote! {
+            #[derive(Clone)]
+            #[command(run = hello)]
+            pub struct Hello<T> { greeting: T }
+        },
+    ).to_string();
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains("# [derive (Clone)] pub struct Hello < T > { greeting : T }"), "{}", res);
+    assert!(res.contains("impl < T > nu_protocol :: engine :: Command for Hello < T > {"), "{}", res);
+    assert!(res.contains(r#"fn name (& self) -> & str { "str hello" }"#), "{}", res);
+    assert!(res.contains("hello (self , engine_state , stack , call , input)"), "{}", res);
+
+    let res = nu_signature_core::make_engine_command(quote! { #source }, quote! { struct StrHello; }).to_string();
+    assert!(res.contains("str_hello (self , engine_state , stack , call , input)"), "{}", res);
+
+    let res = nu_signature_core::make_engine_command(quote! { #source }, quote! { enum Hello {} }).to_string();
+    assert!(res.contains("compile_error"), "{}", res);
+}
+
+#[test]
+fn output_structs() {
+    let source = Literal::string(r#"
+        extern "ls files" []: [
+            nothing -> table<name: string, size: filesize, type: string, owner: record<id: int, groups: list<string>>, pattern: glob>
+            string -> table<name: string, size: filesize, type: string, owner: record<id: int, groups: list<string>>, pattern: glob>
+        ]
+    "#);
+    let res = plugin_command(quote! { #[command(output = File)] pub struct LsFiles for MyPlugin; #source });
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains("pub struct File { pub name : String , pub size : i64 , pub r#type : String , pub owner : FileOwner , pub pattern : nu_protocol :: NuGlob }"), "{}", res);
+    assert!(res.contains("pub struct FileOwner { pub id : i64 , pub groups : Vec < String > }"), "{}", res);
+    assert!(res.contains(r#"record . push ("size" , nu_protocol :: Value :: filesize (self . size , span)) ;"#), "{}", res);
+    assert!(res.contains(r#"record . push ("owner" , nu_protocol :: IntoValue :: into_value (self . owner , span)) ;"#), "{}", res);
+    assert!(res.contains("nu_protocol :: Value :: glob (glob . to_string () , ! glob . is_expand () , span)"), "{}", res);
+    assert!(res.contains("ls_files (plugin , engine , call , input ,) . map (| output | nu_protocol :: PipelineData :: Value (nu_protocol :: IntoValue :: into_value (output , call . head) , None))"), "{}", res);
+
+    let source = Literal::string("extern hello []: [nothing -> record<a: int>, int -> string]");
+    let res = plugin_command(quote! { #[command(output = Hello)] struct Hello for MyPlugin; #source });
+    assert!(res.contains("compile_error"), "{}", res);
+}
+
+#[test]
+fn param_structs() {
+    let source = Literal::string(r#"
+        extern hello [
+            rows: table<name: string, size: filesize>
+            --config: record<name: string, port: int, owner: record<id: int>>
+        ]
+    "#);
+    let res = plugin_command(quote! { pub struct Hello for MyPlugin; #source });
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains("pub struct HelloRows { pub name : String , pub size : i64 }"), "{}", res);
+    assert!(res.contains("pub struct HelloConfig { pub name : String , pub port : i64 , pub owner : HelloConfigOwner }"), "{}", res);
+    assert!(res.contains("pub struct HelloConfigOwner { pub id : i64 }"), "{}", res);
+    assert!(res.contains("impl nu_protocol :: FromValue for HelloConfig"), "{}", res);
+    assert!(res.contains("call . req :: < Vec < HelloRows > > (0usize) ?"), "{}", res);
+    assert!(res.contains(r#"call . get_flag :: < HelloConfig > ("config") ?"#), "{}", res);
+    assert!(res.contains("in the field `owner.id` of the parameter `config`"), "{}", res);
+    assert!(res.contains("missing field `name` of the parameter `rows`"), "{}", res);
+
+    let source = Literal::string("extern hello [--config: record<name: string, comment: any, unset: nothing>]");
+    let res = plugin_command(quote! { pub struct Hello for MyPlugin; #source });
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains("pub struct HelloConfig { pub name : String , pub comment : Option < nu_protocol :: Value > , pub unset : Option < nu_protocol :: Value > }"), "{}", res);
+    assert!(res.contains(r#"comment : match record . remove ("comment") { None | Some (nu_protocol :: Value :: Nothing { .. }) => None , value => value , }"#), "{}", res);
+}
+
+#[test]
+fn colliding_fields() {
+    let source = Literal::string("extern hello [--config: record<a-b: int, a_b: int>]");
+    let res = plugin_command(quote! { pub struct Hello for MyPlugin; #source });
+    assert_eq!(res, r#"compile_error ! ("the columns `a-b` and `a_b` are both the field `a_b` of `HelloConfig`") ;"#);
+
+    let source = Literal::string("extern hello []: nothing -> table<Name: string, owner: record<id: int, ID: int>>");
+    let res = plugin_command(quote! { #[command(output = File)] pub struct Hello for MyPlugin; #source });
+    assert_eq!(res, r#"compile_error ! ("the columns `id` and `ID` are both the field `id` of `FileOwner`") ;"#);
+}
+
+#[test]
+fn allowed_values() {
+    let source = Literal::string(r#"
+        extern convert [
+            --format(-f): string@[json yaml "toml"]  # output format
+        ]
+    "#);
+    let res = plugin_command(quote! { pub struct Convert for MyPlugin; #source });
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains("pub enum ConvertFormat { Json , Yaml , Toml }"), "{}", res);
+    assert!(res.contains(r#"pub const VALUES : & 'static [& 'static str] = & ["json" , "yaml" , "toml"] ;"#), "{}", res);
+    assert!(res.contains(r#""json" => Ok (Self :: Json) ,"#), "{}", res);
+    assert!(res.contains(r#"call . get_flag :: < ConvertFormat > ("format") ?"#), "{}", res);
+    assert!(res.contains("json, yaml, toml"), "{}", res);
+
+    let source = Literal::string("extern convert [format: string@[json yaml], --format: string, --indent(-i): string@[tab space]]");
+    let res = plugin_command(quote! { pub struct Convert for MyPlugin; #source });
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains("call . req :: < ConvertFormat > (0usize) ?"), "{}", res);
+    assert!(res.contains(r#"call . get_flag :: < String > ("format") ?"#), "{}", res);
+    assert!(res.contains(r#"call . get_flag :: < ConvertIndent > ("indent") ?"#), "{}", res);
+
+    let source = Literal::string("extern convert [--kind: string@[self other 2d]]");
+    let res = plugin_command(quote! { pub struct Convert for MyPlugin; #source });
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains("pub enum ConvertKind { Self_ , Other , V2d }"), "{}", res);
+
+    let source = Literal::string("extern convert [--kind: string@[a-b a_b]]");
+    let res = plugin_command(quote! { pub struct Convert for MyPlugin; #source });
+    assert_eq!(res, r#"compile_error ! ("the allowed values `a-b` and `a_b` are both the variant `AB` of `ConvertKind`") ;"#);
+
+    let source = Literal::string("extern convert [--format: int@[1 2]]");
+    let res = plugin_command(quote! { pub struct Convert for MyPlugin; #source });
+    assert!(res.contains("allowed values only apply to `string` parameters"), "{}", res);
+}
+
+#[test]
+fn dispatch() {
+    let source = Literal::string(r#"
+        extern "str size" [--unit: string]: [string -> int, list<string> -> list<int>, list<int> -> list<int>]
+    "#);
+    let res = plugin_command(quote! { #[command(dispatch)] pub struct StrSize for MyPlugin; #source });
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains(r#"if input_type . is_subtype (& nu_protocol :: Type :: String) { return self . on_string (plugin , engine , call , input , call . get_flag :: < String > ("unit") ?) ; }"#), "{}", res);
+    assert!(res.contains("if input_type . is_subtype (& nu_protocol :: Type :: List (Box :: new (nu_protocol :: Type :: String))) || input_type == nu_protocol :: Type :: List (Box :: new (nu_protocol :: Type :: Any)) || matches ! (input , nu_protocol :: PipelineData :: ListStream (..)) || input_type . is_subtype (& nu_protocol :: Type :: List (Box :: new (nu_protocol :: Type :: Int)))"), "{}", res);
+    assert!(!res.contains("is_subtype (& nu_protocol :: Type :: String) ||"), "{}", res);
+    assert!(res.contains("return self . on_list (plugin"), "{}", res);
+    assert!(res.contains(r#"exp_input_type : String :: from ("string, list<string>, list<int>")"#), "{}", res);
+
+    let source = Literal::string("extern hello []: [int -> int, nothing -> int]");
+    let res = plugin_command(quote! { #[command(dispatch)] struct Hello for MyPlugin; #source });
+    assert!(res.contains("impl nu_plugin :: SimplePluginCommand for Hello"), "{}", res);
+    assert!(res.contains("let input_type : nu_protocol :: Type = input . get_type () ;"), "{}", res);
+    assert!(res.contains("return self . on_nothing (plugin"), "{}", res);
+
+    let res = plugin_command(quote! { #[command(dispatch, run = hello)] struct Hello for MyPlugin; #source });
+    assert!(res.contains("compile_error"), "{}", res);
+
+    let source = Literal::string("extern hello []");
+    let res = plugin_command(quote! { #[command(dispatch)] struct Hello for MyPlugin; #source });
+    assert!(res.contains("compile_error"), "{}", res);
+}
+
+#[test]
+fn check_output() {
+    let source = Literal::string("extern hello []: [string -> int, int -> string]");
+    let res = plugin_command(quote! { #[command(check_output)] struct Hello for MyPlugin; #source });
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains("let output : Result < nu_protocol :: Value , nu_protocol :: LabeledError > = (|| { hello (plugin , engine , call , input ,) }) () ;"), "{}", res);
+    assert!(res.contains("[(nu_protocol :: Type :: String , nu_protocol :: Type :: Int) , (nu_protocol :: Type :: Int , nu_protocol :: Type :: String)]"), "{}", res);
+    assert!(res.contains(r#"format ! ("`{}` returned an output of an undeclared type" , "hello")"#), "{}", res);
+
+    let source = Literal::string("extern hello []: [nothing -> list<string>, list<int> -> table<name: string>]");
+    let res = plugin_command(quote! { #[command(check_output)] struct Hello for MyPlugin; #source });
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains("* actual == nu_protocol :: Type :: List (Box :: new (nu_protocol :: Type :: Any)) && matches ! (declared , nu_protocol :: Type :: List (_) | nu_protocol :: Type :: Table (_))"), "{}", res);
+    assert!(res.contains(". filter (| (input , _) | compatible (& input_type , input))"), "{}", res);
+    assert!(res.contains("! expected . iter () . any (| expected | compatible (actual , expected))"), "{}", res);
+
+    let source = Literal::string("extern hello []: [string -> int, int -> string]");
+    let res = plugin_command(quote! { #[command(check_output, dispatch)] struct Hello for MyPlugin; #source });
+    assert!(res.contains("(|| { let input_type : nu_protocol :: Type = input . get_type () ;"), "{}", res);
+
+    let source = Literal::string("extern hello []");
+    let res = plugin_command(quote! { #[command(check_output)] struct Hello for MyPlugin; #source });
+    assert!(res.contains("compile_error"), "{}", res);
+}
+
+#[test]
+fn documentation() {
+    let source = Literal::string(r#"
+        # Say hello
+        extern "str hello" [name: string]: nothing -> string
+    "#);
+    let res = nu_signature_core::make_engine_command(quote! { #source }, quote! { #[derive(Clone)] struct StrHello; }).to_string();
+    assert!(res.contains(r#"# [doc = "Say hello"] # [doc = ""] # [doc = "```text\nUsage:\n  > str hello <name> \n"#), "{}", res);
+    assert!(res.contains(r#"\nParameters:\n  name <string>: \n"#), "{}", res);
+    assert!(res.contains(r#"\nFlags:\n  -h, --help - Display the help message for this command\n"#), "{}", res);
+    assert!(!res.contains("...args"), "{}", res);
+    assert!(res.contains("│ 0 │ nothing │ string │"), "{}", res);
+    assert!(res.contains("```\"] # [derive (Clone)] struct StrHello ;"), "{}", res);
+}
\ No newline at end of file
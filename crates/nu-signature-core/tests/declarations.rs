@@ -0,0 +1,73 @@
+use proc_macro2::{Literal, TokenTree};
+
+fn make_signature(source: &str) -> String {
+    let item = TokenTree::Literal(Literal::string(source));
+    nu_signature_core::make_signature(item.into()).to_string()
+}
+
+#[test]
+fn def_and_export_match_extern() {
+    let params = r#"hello [
+        pos_arg: int    # pos_arg description
+        --switch        # switch description
+        --flag: string  # flag description
+    ]: nothing -> string"#;
+    let expected = make_signature(&format!("# description\nextern {}", params));
+    assert!(!expected.contains("compile_error"), "{}", expected);
+
+    for source in [
+        format!("# description\nexport extern {}", params),
+        format!("# description\ndef {} {{ \"hello\" }}", params),
+        format!("# description\nexport def {} {{ 'hello' }}", params),
+    ] {
+        assert_eq!(make_signature(&source), expected, "for:\n{}", source);
+    }
+}
+
+#[test]
+fn subcommand_names() {
+    let quoted = make_signature(r#"extern "matrix hello" [x: int]"#);
+    assert!(quoted.contains(r#"Signature :: build ("matrix hello")"#), "{}", quoted);
+
+    let module = make_signature(r#"module matrix { export extern hello [x: int] }"#);
+    assert_eq!(module, quoted);
+
+    let nested = make_signature(r#"module polars { export module into { export extern df [] } }"#);
+    assert!(nested.contains(r#"Signature :: build ("polars into df")"#), "{}", nested);
+}
+
+#[test]
+fn constants_in_defaults() {
+    let with_const = make_signature(r#"
+        const LIMIT = 100
+        const FORMATS = [json yaml]
+        extern hello [--limit: int = $LIMIT, --formats: list<string> = $FORMATS]
+    "#);
+    let inline = make_signature(r#"extern hello [--limit: int = 100, --formats: list<string> = [json yaml]]"#);
+    assert!(!with_const.contains("compile_error"), "{}", with_const);
+    assert_eq!(with_const, inline);
+}
+
+
+#[test]
+fn completer_helpers() {
+    let sig = make_signature(r#"
+        def formats [] { [json yaml] }
+        # converts
+        extern convert [format: string@formats]
+    "#);
+    assert!(sig.contains("CompleterWrapper"), "{}", sig);
+    assert!(sig.contains(r#"Signature :: build ("convert")"#), "{}", sig);
+}
+
+#[test]
+fn allowed_values() {
+    let sig = make_signature(r#"
+        # converts
+        extern convert [format: string@[json, yaml, 'toml'] --indent: int]
+    "#);
+    assert!(sig.contains(r#"name : String :: from ("format") , shape : nu_protocol :: SyntaxShape :: String ,"#), "{}", sig);
+    assert!(!sig.contains("CompleterWrapper"), "{}", sig);
+    assert!(sig.contains(r#". description ("converts")"#), "{}", sig);
+    assert!(!sig.contains("__nu_signature_values_"), "{}", sig);
+}
\ No newline at end of file
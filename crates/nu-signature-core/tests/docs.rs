@@ -0,0 +1,109 @@
+use nu_signature_core::docs::{self, Format};
+
+const SOURCE: &str = r#"
+const LIMIT = 10
+
+def formats [] { [json yaml] }
+
+# Convert the input
+@category formats
+@example "Convert to json" { ls | convert json } --result "[]"
+extern convert [
+    format: string@formats           # output format
+    --limit(-l): int = $LIMIT        # maximum number of rows
+    --pretty                         # indent the output
+]: [table -> string, list<string> -> string]
+
+# Say hello
+@category strings
+extern "str hello" [name?: string = "world", ...rest: int]; extern other []
+"#;
+
+#[test]
+fn nu_declarations() {
+    let declarations = nu_signature_core::nu_declarations(SOURCE).unwrap();
+    let names = declarations.iter().map(|d| d.signature.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["convert", "str hello", "other"]);
+    assert_eq!(declarations[0].signature.description, "Convert the input");
+    assert_eq!(declarations[0].examples.len(), 1);
+    assert_eq!(declarations[1].signature.category.to_string(), "strings");
+    assert!(declarations[2].examples.is_empty());
+
+    assert!(nu_signature_core::nu_declarations("ls | length").is_err());
+}
+
+#[test]
+fn markdown_page() {
+    let declarations = nu_signature_core::nu_declarations(SOURCE).unwrap();
+    let page = docs::page(&declarations[0], Format::Markdown);
+    assert!(page.starts_with("# convert\n\nConvert the input\n\nCategory: `formats`"), "{}", page);
+    assert!(page.contains("## Usage\n\n```nushell\n> convert {flags} <format>\n```"), "{}", page);
+    assert!(page.contains("| `--limit` | `-l` | `int` | `10` | maximum number of rows |"), "{}", page);
+    assert!(page.contains("| `--pretty` |  | `switch` |  | indent the output |"), "{}", page);
+    assert!(page.contains("| `format` | `string` | yes |  | output format |"), "{}", page);
+    assert!(page.contains("| `list<string>` | `string` |"), "{}", page);
+    assert!(page.contains("Convert to json\n\n```nushell\n> ls | convert json\n```\n\nResult:\n\n```nushell\n\"[]\"\n```"), "{}", page);
+    assert!(!page.contains("--help"), "{}", page);
+
+    let page = docs::page(&declarations[1], Format::Markdown);
+    assert!(page.contains("> str hello (name) ...(rest)"), "{}", page);
+    assert!(page.contains(r#"| `name` | `string` | no | `"world"` |  |"#), "{}", page);
+    assert!(page.contains("| `...rest` | `int` | no |  |  |"), "{}", page);
+}
+
+#[test]
+fn html_page_and_index() {
+    let declarations = nu_signature_core::nu_declarations(SOURCE).unwrap();
+    let page = docs::page(&declarations[0], Format::Html);
+    assert!(page.contains("<title>convert</title>"), "{}", page);
+    assert!(page.contains("<tr><td><code>list&lt;string&gt;</code></td><td><code>string</code></td></tr>"), "{}", page);
+
+    let files = docs::render(&declarations, Format::Html).unwrap();
+    let names = files.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["convert.html", "str-hello.html", "other.html", "index.html"]);
+
+    let colliding = nu_signature_core::nu_declarations("extern 'str hello' []; extern str-hello []").unwrap();
+    let error = docs::render(&colliding, Format::Markdown).unwrap_err();
+    assert_eq!(error, "the pages of `str hello` and `str-hello` would both be written to `str-hello.md`");
+    let colliding = nu_signature_core::nu_declarations("extern index []").unwrap();
+    assert!(docs::render(&colliding, Format::Markdown).is_err());
+
+    let index = docs::index(&declarations, Format::Markdown);
+    let experimental = index.find("## experimental").unwrap();
+    let formats = index.find("## formats").unwrap();
+    let strings = index.find("## strings").unwrap();
+    assert!(experimental < formats && formats < strings, "{}", index);
+    assert!(index.contains("| [str hello](str-hello.md) | Say hello |"), "{}", index);
+}
+
+#[test]
+fn rust_declarations() {
+    let source = r####"
+        fn signature(&self) -> Signature {
+            nu_signature::signature!(r#"extern hello [--port: int = {PORT}]"#, PORT = 8080)
+        }
+
+        plugin_command! {
+            #[command(simple)]
+            pub struct Add for MyPlugin;
+            r#"
+                @example "Add one" { 1 | add 1 } --result 2
+                extern add [value: int]: int -> int
+            "#
+        }
+
+        #[nu_signature::engine_command("extern \"str hello\" []")]
+        #[derive(Clone)]
+        struct StrHello;
+    "####;
+    let declarations = nu_signature_core::rust_declarations(source).unwrap();
+    let names = declarations.iter().map(|d| d.signature.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["hello", "add", "str hello"]);
+    assert_eq!(declarations[1].examples.len(), 1);
+
+    let page = docs::page(&declarations[0], Format::Markdown);
+    assert!(page.contains("| `--port` |  | `int` | `{PORT}` |  |"), "{}", page);
+
+    let error = nu_signature_core::rust_declarations(r#"signature!("extern")"#).err().unwrap();
+    assert!(!error.contains("compile_error"), "{}", error);
+}
\ No newline at end of file
@@ -0,0 +1,65 @@
+use nu_signature_core::help::help;
+
+#[test]
+fn help_text() {
+    let sig = nu_signature_core::source_signature(r#"
+        # Convert the input
+        @search-terms serialize
+        extern convert [
+            format: string                   # output format
+            indent?: int = 2                 # indentation
+            ...files: path                   # files to convert
+            --limit(-l): int = 10            # maximum number of rows
+            --pretty                         # indent the output
+        ]: [table -> string, list<string> -> string]
+    "#).unwrap();
+    let expected = "\
+Convert the input
+
+Search terms: serialize
+
+Usage:
+  > convert {flags} <format> (indent) ...(files) 
+
+Flags:
+  -l, --limit <Int> - maximum number of rows (default: 10)
+  --pretty - indent the output
+  -h, --help - Display the help message for this command
+
+Parameters:
+  format <string>: output format
+  indent <int>: indentation (optional, default: 2)
+  ...files <path>: files to convert
+
+Input/output types:
+  ╭───┬──────────────┬────────╮
+  │ # │    input     │ output │
+  ├───┼──────────────┼────────┤
+  │ 0 │ table        │ string │
+  │ 1 │ list<string> │ string │
+  ╰───┴──────────────┴────────╯
+
+";
+    assert_eq!(help(&sig), expected);
+}
+
+#[test]
+fn help_without_parameters() {
+    let sig = nu_signature_core::source_signature("extern hello [...rest]: nothing -> string").unwrap();
+    let help = help(&sig);
+    assert!(help.starts_with("Usage:\n  > hello ...(rest) \n\nFlags:\n  -h, --help"), "{}", help);
+    assert!(help.contains("\nParameters:\n  ...rest <any>: \n"), "{}", help);
+}
+
+#[test]
+fn help_of_an_extern_without_rest() {
+    let source = "extern hello [name: string]";
+    let (_, sig) = nu_signature_core::builtin_parse::extract_declaration(source.as_bytes()).unwrap();
+    let help = help(&sig);
+    assert_eq!(help, "Usage:\n  > hello <name> \n\nFlags:\n  -h, --help - Display the help message for this command\n\nParameters:\n  name <string>: \n\n");
+    #[cfg(feature = "nu-parser")]
+    {
+        let (_, sig) = nu_signature_core::parse::extract_declaration(source.as_bytes()).unwrap();
+        assert_eq!(nu_signature_core::help::help(&sig), help);
+    }
+}
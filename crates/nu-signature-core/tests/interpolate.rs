@@ -0,0 +1,37 @@
+use proc_macro2::Literal;
+use quote::quote;
+
+#[test]
+fn rust_constants_in_defaults_and_descriptions() {
+    let source = Literal::string(r#"
+        # Serve on {PORT}, version {VERSION}
+        extern serve [
+            --port: int = {PORT}          # port, {PORT} by default
+            --record: record = {a: 1}     # not interpolated: {a: 1}
+            name?: string = "{PORT}"      # not interpolated either: {UNKNOWN}
+        ]
+    "#);
+    let res = nu_signature_core::make_signature(quote! { #source, PORT = DEFAULT_PORT, VERSION }).to_string();
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains("nu_protocol :: IntoValue :: into_value (DEFAULT_PORT , nu_protocol :: Span :: unknown ())"), "{}", res);
+    assert!(res.contains(r#"format ! ("Serve on {}, version {}" , DEFAULT_PORT , VERSION)"#), "{}", res);
+    assert!(res.contains(r#"format ! ("port, {} by default" , DEFAULT_PORT)"#), "{}", res);
+    assert!(res.contains(r#"String :: from ("not interpolated: {a: 1}")"#), "{}", res);
+    assert!(res.contains(r#"Value :: string ("{PORT}"#), "{}", res);
+    assert!(res.contains(r#"String :: from ("not interpolated either: {UNKNOWN}")"#), "{}", res);
+}
+
+#[test]
+fn placeholders_need_opt_in() {
+    let source = Literal::string("extern serve [--port: int = {PORT}]");
+    let res = nu_signature_core::make_signature(quote! { #source }).to_string();
+    assert!(res.contains("compile_error"), "{}", res);
+}
+
+#[test]
+fn description_on_the_first_line() {
+    let source = Literal::string("# Serve on {PORT}\nextern serve [--port: int = {PORT}]");
+    let res = nu_signature_core::make_signature(quote! { #source, PORT }).to_string();
+    assert!(!res.contains("compile_error"), "{}", res);
+    assert!(res.contains(r#"format ! ("Serve on {}" , PORT)"#), "{}", res);
+}
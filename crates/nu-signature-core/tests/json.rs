@@ -0,0 +1,45 @@
+#![cfg(feature = "serde")]
+use nu_signature_core::json::{self, Flag, Positional};
+
+#[test]
+fn export() {
+    let declarations = nu_signature_core::nu_declarations(r#"
+        # Convert the input
+        @category formats
+        @example "Convert a record" { {a: 1} | convert json } --result '{"a":1}'
+        extern convert [
+            format: string                   # output format
+            indent?: int = 2
+            --fields(-f): list<string> = [name size]
+            --pretty
+        ]: [table -> string, list<string> -> string]
+    "#).unwrap();
+    let export = json::export(&declarations);
+    assert_eq!(export.version, json::SCHEMA_VERSION);
+    let command = &export.commands[0];
+    assert_eq!(command.name, "convert");
+    assert_eq!(command.category, "formats");
+    assert_eq!(command.required_positional, [Positional {
+        name: "format".to_string(),
+        shape: "string".to_string(),
+        description: "output format".to_string(),
+        default: None,
+    }]);
+    assert_eq!(command.optional_positional[0].default.as_deref(), Some("2"));
+    assert_eq!(command.rest_positional, None);
+    assert_eq!(command.flags[0], Flag {
+        long: "fields".to_string(),
+        short: Some('f'),
+        shape: Some("list<string>".to_string()),
+        required: false,
+        description: String::new(),
+        default: Some(r#"["name", "size"]"#.to_string()),
+    });
+    assert_eq!(command.flags[1].shape, None);
+    assert_eq!(command.input_output_types[1].input, "list<string>");
+    assert_eq!(command.examples[0].result.as_deref(), Some(r#""{\"a\":1}""#));
+
+    let json = json::to_json(&declarations);
+    assert!(json.starts_with("{\n  \"version\": 1,\n  \"commands\": ["), "{}", json);
+    assert_eq!(serde_json::from_str::<json::Export>(&json).unwrap(), export);
+}
\ No newline at end of file
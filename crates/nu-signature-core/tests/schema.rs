@@ -0,0 +1,90 @@
+#![cfg(feature = "serde")]
+use nu_signature_core::schema;
+use serde_json::json;
+
+#[test]
+fn arguments_schema() {
+    let declarations = nu_signature_core::nu_declarations(r#"
+        # Convert the input
+        extern convert [
+            format: string                        # output format
+            path?: path = "out.json"
+            ...columns: string@[name size]
+            --fields(-f): list<string> = [name size]
+            --limit(-l): int = 10                 # maximum number of rows
+            --options: record<indent: int, sort: bool>
+            --ratio: float
+            --timeout: duration = 1sec            # time to wait
+            --sizes: list<filesize>
+            --pretty
+        ]
+    "#).unwrap();
+    let schema = schema::arguments_schema(&declarations[0]).unwrap();
+    assert_eq!(schema["$schema"], schema::SCHEMA_DIALECT);
+    assert_eq!(schema["title"], "convert");
+    assert_eq!(schema["description"], "Convert the input");
+    assert_eq!(schema["type"], "object");
+    assert_eq!(schema["additionalProperties"], false);
+    assert_eq!(schema["required"], json!(["format"]));
+
+    let properties = &schema["properties"];
+    assert_eq!(properties["format"], json!({ "type": "string", "description": "output format" }));
+    assert_eq!(properties["path"], json!({ "type": "string", "x-nu-shape": "path", "default": "out.json" }));
+    assert_eq!(properties["columns"], json!({
+        "type": "array",
+        "items": { "type": "string", "enum": ["name", "size"] },
+    }));
+    assert_eq!(properties["fields"], json!({
+        "type": "array",
+        "items": { "type": "string" },
+        "default": ["name", "size"],
+    }));
+    assert_eq!(properties["limit"], json!({ "type": "integer", "description": "maximum number of rows", "default": 10 }));
+    assert_eq!(properties["timeout"], json!({
+        "type": "integer",
+        "x-nu-shape": "duration",
+        "description": "time to wait (duration in nanoseconds)",
+        "default": 1_000_000_000,
+    }));
+    assert_eq!(properties["sizes"], json!({
+        "type": "array",
+        "items": { "type": "integer", "x-nu-shape": "filesize", "description": "file size in bytes" },
+    }));
+    assert_eq!(properties["options"], json!({
+        "type": "object",
+        "properties": { "indent": { "type": "integer" }, "sort": { "type": "boolean" } },
+        "required": ["indent", "sort"],
+    }));
+    assert_eq!(properties["ratio"], json!({ "type": "number" }));
+    assert_eq!(properties["pretty"], json!({ "type": "boolean", "default": false }));
+    assert!(properties.get("help").is_none());
+}
+
+#[test]
+fn placeholder_defaults() {
+    let declarations = nu_signature_core::rust_declarations(
+        r####"signature!(r#"extern hello [--port: int = {PORT}]"#, PORT = 8080)"####,
+    )
+    .unwrap();
+    let schema = schema::arguments_schema(&declarations[0]).unwrap();
+    assert_eq!(schema["properties"]["port"], json!({ "type": "integer" }));
+    assert_eq!(schema["required"], json!([]));
+    assert!(schema.get("description").is_none());
+}
+
+#[test]
+fn allowed_values_and_conflicts() {
+    let declarations = nu_signature_core::nu_declarations(r#"
+        extern convert [format: string@[json yaml], --format(-f): string@["toml", 'csv'], --indent: string]
+    "#).unwrap();
+    let error = schema::arguments_schema(&declarations[0]).unwrap_err();
+    assert!(error.contains("several parameters of `convert` are named `format`"), "{}", error);
+
+    let declarations = nu_signature_core::nu_declarations(r#"
+        extern convert [format: string@[json yaml], --to(-t): string@["toml", 'csv'], --indent: string]
+    "#).unwrap();
+    let schema = schema::arguments_schema(&declarations[0]).unwrap();
+    assert_eq!(schema["properties"]["format"], json!({ "type": "string", "enum": ["json", "yaml"] }));
+    assert_eq!(schema["properties"]["to"], json!({ "type": "string", "enum": ["toml", "csv"] }));
+    assert_eq!(schema["properties"]["indent"], json!({ "type": "string" }));
+}
@@ -0,0 +1,19 @@
+//! Snapshots of the generated code for every fixture in `tests/fixtures`, so that codegen changes can be reviewed.
+//! Review the changes with `cargo insta review`.
+#![cfg(all(feature = "nu-parser", not(feature = "builtin-parser")))]
+
+use proc_macro2::{Literal, TokenTree};
+use quote::quote;
+
+#[test]
+fn build_signature_output() {
+    insta::glob!("fixtures/*.nu", |path| {
+        let source = std::fs::read_to_string(path).expect("failed to read the fixture");
+        let tokens = nu_signature_core::make_signature(TokenTree::Literal(Literal::string(&source)).into());
+        let file = syn::parse2::<syn::File>(quote! {
+            fn signature() -> nu_protocol::Signature #tokens
+        })
+        .unwrap_or_else(|e| panic!("invalid generated code for {}: {}\n{}", path.display(), e, tokens));
+        insta::assert_snapshot!(prettyplease::unparse(&file));
+    });
+}
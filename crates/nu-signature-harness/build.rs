@@ -0,0 +1,31 @@
+//! Generates a function per fixture of `nu-signature-core/tests/fixtures`, with the code `make_signature` generates for
+//! it as body. A fixture generating invalid code makes this crate fail to compile.
+use std::{env, fs, path::Path};
+
+use proc_macro2::{Literal, TokenTree};
+
+fn main() {
+    let fixtures_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../nu-signature-core/tests/fixtures");
+    println!("cargo:rerun-if-changed={}", fixtures_dir.display());
+
+    let mut paths = fs::read_dir(&fixtures_dir)
+        .expect("failed to read the fixtures directory")
+        .map(|entry| entry.expect("failed to read the fixtures directory").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "nu"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut functions = String::new();
+    let mut fixtures = String::new();
+    for path in &paths {
+        let source = fs::read_to_string(path).expect("failed to read the fixture");
+        let name = path.file_stem().unwrap().to_string_lossy().replace('-', "_");
+        let body = nu_signature_core::make_signature(TokenTree::Literal(Literal::string(&source)).into());
+        functions.push_str(&format!("pub fn {}() -> nu_protocol::Signature {}\n\n", name, body));
+        fixtures.push_str(&format!("    Fixture {{ name: {:?}, source: include_str!({:?}), signature: {} }},\n", name, path.canonicalize().unwrap(), name));
+    }
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("fixtures.rs");
+    fs::write(out, format!("{}pub const FIXTURES: &[Fixture] = &[\n{}];\n", functions, fixtures))
+        .expect("failed to write the generated fixtures");
+}
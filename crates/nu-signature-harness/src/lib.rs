@@ -0,0 +1,10 @@
+//! Code generated by `make_signature` for every fixture of `nu-signature-core/tests/fixtures`, compiled, so the
+//! signatures it builds can be compared with the ones nu-parser produces.
+
+pub struct Fixture {
+    pub name: &'static str,
+    pub source: &'static str,
+    pub signature: fn() -> nu_protocol::Signature,
+}
+
+include!(concat!(env!("OUT_DIR"), "/fixtures.rs"));
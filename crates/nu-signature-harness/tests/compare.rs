@@ -0,0 +1,25 @@
+//! The code generated for each fixture must build the same signature as the one parsed at compile time.
+use nu_signature_core::comparable_signature;
+use nu_signature_harness::FIXTURES;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn generated_signature_equals_parsed_signature() {
+    assert!(!FIXTURES.is_empty(), "no fixture found");
+    for fixture in FIXTURES {
+        let expected = nu_signature_core::source_signature(fixture.source)
+            .unwrap_or_else(|e| panic!("failed to parse the fixture `{}`: {}", fixture.name, e));
+        let (expected, sig) = (comparable_signature(expected), comparable_signature((fixture.signature)()));
+
+        assert_eq!(sig.name, expected.name, "name mismatch for `{}`", fixture.name);
+        assert_eq!(sig.description, expected.description, "description mismatch for `{}`", fixture.name);
+        assert_eq!(sig.extra_description, expected.extra_description, "extra description mismatch for `{}`", fixture.name);
+        assert_eq!(sig.search_terms, expected.search_terms, "search terms mismatch for `{}`", fixture.name);
+        assert_eq!(sig.category, expected.category, "category mismatch for `{}`", fixture.name);
+        assert_eq!(sig.required_positional, expected.required_positional, "required positionals mismatch for `{}`", fixture.name);
+        assert_eq!(sig.optional_positional, expected.optional_positional, "optional positionals mismatch for `{}`", fixture.name);
+        assert_eq!(sig.rest_positional, expected.rest_positional, "rest positional mismatch for `{}`", fixture.name);
+        assert_eq!(sig.named, expected.named, "flags mismatch for `{}`", fixture.name);
+        assert_eq!(sig.input_output_types, expected.input_output_types, "input/output types mismatch for `{}`", fixture.name);
+    }
+}
@@ -9,10 +9,16 @@ fuzz_target!(|data: Signature| {
     SHORTS.lock().map(|mut s| s.clear()).expect("failed to clear shorts");
     let data_token = proc_macro2::TokenTree::Literal(proc_macro2::Literal::string(&data.to_string()));
     let res = nu_signature_core::make_signature(data_token.clone().into());
-    let res = res.to_string();
-    assert!(!res.contains("compile_error"), "Failed to make signature:\nnu signature:\n{}\nTokenTree:\n{:?}\nRust signature:\n{}", data, data_token, res);
+    let res_str = res.to_string();
+    assert!(!res_str.contains("compile_error"), "Failed to make signature:\nnu signature:\n{}\nTokenTree:\n{:?}\nRust signature:\n{}", data, data_token, res_str);
+    if let Err(e) = syn::parse2::<syn::Expr>(res) {
+        panic!("Generated signature is not a valid Rust expression: {}\nnu signature:\n{}\nRust signature:\n{}", e, data, res_str);
+    }
 });
 
+/// Name of the helper command used as completer by the parameters.
+const COMPLETER: &str = "complete";
+
 #[derive(arbitrary::Arbitrary, Debug)]
 struct Signature {
     name: Named,
@@ -21,6 +27,7 @@ struct Signature {
 
 impl Display for Signature {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "def {} [] {{ [] }}", COMPLETER)?;
         write!(f, "extern {} {}", self.name, self.signature)
     }
 }
@@ -31,6 +38,7 @@ struct Parameters {
     pub optional_positional: Vec<OptionalPositionalArg>,
     pub rest_positional: Option<RestParameter>,
     pub named: Vec<Flag>,
+    pub short_flags: Vec<ShortFlag>,
     pub input_output_types: Vec<(ReturnType, ReturnType)>,
 }
 
@@ -49,6 +57,9 @@ impl Display for Parameters {
         for param in &self.named {
             write!(f, "\n{}", param)?;
         }
+        for param in &self.short_flags {
+            write!(f, "\n{}", param)?;
+        }
         write!(f, "\n]")?;
         match self.input_output_types.len() {
             0 => write!(f, ";"),
@@ -70,6 +81,8 @@ struct Flag {
     short: bool,
     desc: Option<Named>,
     ty: Option<ParameterType>,
+    default: Option<Value>,
+    completer: bool,
 }
 static SHORTS: once_cell::sync::Lazy<std::sync::Mutex<std::collections::HashSet<char>>> = once_cell::sync::Lazy::new(|| std::sync::Mutex::new(std::collections::HashSet::<char>::new()));
 
@@ -97,6 +110,28 @@ impl Display for Flag {
         if self.short {
             write!(f, "(-{})", get_short(&self.name))?;
         }
+        match (&self.default, &self.ty) {
+            (Some(value), _) => write!(f, ": {} = {}", value.ty(), value)?,
+            (None, Some(ty)) => write_type(f, ty, self.completer)?,
+            (None, None) => {}
+        }
+        if let Some(desc) = &self.desc {
+            write!(f, " # {}", desc)?;
+        }
+        Ok(())
+    }
+}
+
+/// Flag with only a short name.
+#[derive(arbitrary::Arbitrary, Debug)]
+struct ShortFlag {
+    desc: Option<Named>,
+    ty: Option<ParameterType>,
+}
+
+impl Display for ShortFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "-{}", get_short(""))?;
         if let Some(ty) = &self.ty {
             write!(f, ": {}", ty)?;
         }
@@ -107,18 +142,29 @@ impl Display for Flag {
     }
 }
 
+fn write_type(f: &mut std::fmt::Formatter<'_>, ty: &ParameterType, completer: bool) -> std::fmt::Result {
+    write!(f, ": {}", ty)?;
+    // nu-parser only accepts completers after the simple types
+    let collection = matches!(ty, ParameterType::List(_) | ParameterType::Record(_) | ParameterType::Table(_));
+    if completer && !collection {
+        write!(f, "@{}", COMPLETER)?;
+    }
+    Ok(())
+}
+
 #[derive(arbitrary::Arbitrary, Debug)]
 struct PositionalArg {
     name: Named,
     desc: Option<Named>,
     ty: Option<ParameterType>,
+    completer: bool,
 }
 
 impl Display for PositionalArg {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.name)?;
         if let Some(ty) = &self.ty {
-            write!(f, ": {}", ty)?;
+            write_type(f, ty, self.completer)?;
         }
         if let Some(desc) = &self.desc {
             write!(f, " # {}", desc)?;
@@ -136,7 +182,10 @@ struct OptionalPositionalArg {
 
 impl Display for OptionalPositionalArg {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}?: {}", self.name, self.ty)?;
+        match &self.ty {
+            OptionalValue::HasValue(_) => write!(f, "{}: {}", self.name, self.ty)?,
+            OptionalValue::NoValue(_) => write!(f, "{}?: {}", self.name, self.ty)?,
+        }
         if let Some(desc) = &self.desc {
             write!(f, " # {}", desc)?;
         }
@@ -184,6 +233,7 @@ enum ParameterType {
     List(Option<Box<ParameterType>>),
     Record(Vec<(Named, ParameterType)>),
     Table(Vec<(Named, ParameterType)>),
+    Closure,
     // Nothing,
 }
 impl Display for ParameterType {
@@ -209,7 +259,7 @@ impl Display for ParameterType {
                     return Ok(());
                 }
                 write!(f, "<")?;
-                for (i, (name, dt)) in fields.iter().enumerate() {
+                for (i, (name, dt)) in unique_fields(fields).enumerate() {
                     if i > 0 {
                         write!(f, ", ")?;
                     }
@@ -217,6 +267,7 @@ impl Display for ParameterType {
                 }
                 write!(f, ">")
             }
+            ParameterType::Closure => write!(f, "closure"),
             // DataType::Nothing => write!(f, "nothing"),
         }
     }
@@ -256,7 +307,7 @@ impl Display for ReturnType {
                     return Ok(());
                 }
                 write!(f, "<")?;
-                for (i, (name, dt)) in fields.iter().enumerate() {
+                for (i, (name, dt)) in unique_fields(fields).enumerate() {
                     if i > 0 {
                         write!(f, ", ")?;
                     }
@@ -269,7 +320,13 @@ impl Display for ReturnType {
     }
 }
 
-#[derive(arbitrary::Arbitrary, Debug)]
+/// Fields with their first occurrence only, duplicated columns being an error in nu.
+fn unique_fields<T>(fields: &[(Named, T)]) -> impl Iterator<Item = &(Named, T)> {
+    let mut seen = std::collections::HashSet::new();
+    fields.iter().filter(move |(name, _)| seen.insert(name.0.clone()))
+}
+
+#[derive(arbitrary::Arbitrary, Clone, Debug)]
 enum Value {
     Integer(i64),
     Float(Float),
@@ -293,11 +350,12 @@ impl Display for Value {
                 Ok(())
             }
             Value::Record(r) => {
-                write!(f, "{{{}}}", r.iter().map(|(name, value)| format!("{}: {}", name, value)).collect::<Vec<_>>().join(", "))?;
+                write!(f, "{{{}}}", unique_fields(r).map(|(name, value)| format!("{}: {}", name, value)).collect::<Vec<_>>().join(", "))?;
                 Ok(())
             }
+            // a single row, so that every row has the same columns
             Value::Table(t) => {
-                write!(f, "[{}]", t.iter().map(|(name, value)| format!("{{{}: {}}}", name, value)).collect::<Vec<_>>().join(", "))?;
+                write!(f, "[{}]", Value::Record(t.clone()))?;
                 Ok(())
             }
             // Value::Nothing => write!(f, "null"),
@@ -311,9 +369,13 @@ impl Value {
             Value::Float(_) => ParameterType::Float,
             Value::String(_) => ParameterType::String,
             // Value::Boolean(_) => DataType::Boolean,
-            Value::List(v) => ParameterType::List(if v.is_empty() { None } else { Some(Box::new(v[0].ty())) }),
-            Value::Record(fields) => ParameterType::Record(fields.iter().map(|(name, value)| (name.clone(), value.ty())).collect()),
-            Value::Table(fields) => ParameterType::Table(fields.iter().map(|(name, value)| (name.clone(), value.ty())).collect()),
+            Value::List(v) => {
+                // nu types a list of mixed values as `list<any>`, which the generator can't write as `any` isn't generated
+                let types = v.iter().map(|value| value.ty().to_string()).collect::<std::collections::HashSet<_>>();
+                ParameterType::List(if types.len() == 1 { Some(Box::new(v[0].ty())) } else { None })
+            }
+            Value::Record(fields) => ParameterType::Record(unique_fields(fields).map(|(name, value)| (name.clone(), value.ty())).collect()),
+            Value::Table(fields) => ParameterType::Table(unique_fields(fields).map(|(name, value)| (name.clone(), value.ty())).collect()),
             // Value::Nothing => DataType::Nothing,
         }
     }
@@ -0,0 +1,110 @@
+#![no_main]
+//! Differential fuzzing of the decoding of the literal given to the macros, against `syn`, which unescapes like rustc.
+
+use std::fmt::Display;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: StringLiteral| {
+    let text = data.to_string();
+    let lit = syn::parse_str::<syn::LitStr>(&text)
+        .unwrap_or_else(|e| panic!("Generated an invalid literal: {}\nliteral:\n{}", e, text));
+    // the text the macros receive from the token stream
+    let token = lit.token().to_string();
+    let res = nu_signature_core::literal_to_string(&token);
+    assert_eq!(res.as_deref(), Ok(lit.value().as_str()), "Failed to decode the literal:\n{}\nTokenTree:\n{:?}", text, lit.token());
+});
+
+#[derive(arbitrary::Arbitrary, Debug)]
+enum StringLiteral {
+    Normal(Vec<Piece>),
+    Raw { hashes: u8, content: String },
+}
+
+impl Display for StringLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StringLiteral::Normal(pieces) => {
+                write!(f, "\"")?;
+                for piece in pieces {
+                    write!(f, "{}", piece)?;
+                }
+                write!(f, "\"")
+            }
+            StringLiteral::Raw { hashes, content } => {
+                // carriage returns are not allowed alone in raw strings
+                let content = content.replace('\r', "");
+                // enough hashes so that no quote in the content closes the literal
+                let needed = content
+                    .split('"')
+                    .skip(1)
+                    .map(|after_quote| after_quote.len() - after_quote.trim_start_matches('#').len() + 1)
+                    .max()
+                    .unwrap_or(0);
+                let hashes = "#".repeat(needed.max(*hashes as usize % 8));
+                write!(f, "r{0}\"{1}\"{0}", hashes, content)
+            }
+        }
+    }
+}
+
+#[derive(arbitrary::Arbitrary, Debug)]
+enum Piece {
+    Char(char),
+    Quote,
+    SingleQuote,
+    Backslash,
+    Newline,
+    CarriageReturn,
+    Tab,
+    Null,
+    Ascii(u8),
+    Unicode(char),
+    LineContinuation(Vec<Whitespace>),
+}
+
+impl Display for Piece {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Piece::Char('"') => write!(f, "\\\""),
+            Piece::Char('\\') => write!(f, "\\\\"),
+            // carriage returns are only allowed before a new line, normalized to a new line
+            Piece::Char('\r') => write!(f, "\r\n"),
+            Piece::Char(c) => write!(f, "{}", c),
+            Piece::Quote => write!(f, "\\\""),
+            Piece::SingleQuote => write!(f, "\\'"),
+            Piece::Backslash => write!(f, "\\\\"),
+            Piece::Newline => write!(f, "\\n"),
+            Piece::CarriageReturn => write!(f, "\\r"),
+            Piece::Tab => write!(f, "\\t"),
+            Piece::Null => write!(f, "\\0"),
+            Piece::Ascii(b) if b & 0x80 == 0 => write!(f, "\\x{:02x}", b),
+            Piece::Ascii(b) => write!(f, "\\x{:02X}", b & 0x7F),
+            Piece::Unicode(c) => write!(f, "\\u{{{:x}}}", *c as u32),
+            Piece::LineContinuation(whitespaces) => {
+                write!(f, "\\\n")?;
+                for whitespace in whitespaces {
+                    write!(f, "{}", whitespace)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(arbitrary::Arbitrary, Debug)]
+enum Whitespace {
+    Space,
+    Tab,
+    Newline,
+}
+
+impl Display for Whitespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Whitespace::Space => write!(f, " "),
+            Whitespace::Tab => write!(f, "\t"),
+            Whitespace::Newline => write!(f, "\n"),
+        }
+    }
+}
\ No newline at end of file
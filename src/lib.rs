@@ -1,4 +1,19 @@
 #[proc_macro]
 pub fn signature(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     nu_signature_core::make_signature(item.into()).into()
+}
+
+#[proc_macro]
+pub fn examples(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    nu_signature_core::make_examples(item.into()).into()
+}
+
+#[proc_macro]
+pub fn plugin_command(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    nu_signature_core::make_plugin_command(item.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn engine_command(attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    nu_signature_core::make_engine_command(attr.into(), item.into()).into()
 }
\ No newline at end of file
@@ -0,0 +1,41 @@
+use nu_plugin::{EngineInterface, EvaluatedCall, Plugin, PluginCommand};
+use nu_protocol::{LabeledError, PipelineData};
+
+pub struct MyPlugin;
+
+impl Plugin for MyPlugin {
+    fn version(&self) -> String {
+        String::from("0.1.0")
+    }
+
+    fn commands(&self) -> Vec<Box<dyn PluginCommand<Plugin = Self>>> {
+        vec![Box::new(StrSize)]
+    }
+}
+
+nu_signature::plugin_command! {
+    #[command(dispatch, check_output)]
+    pub struct StrSize for MyPlugin;
+    r#"extern "str size" [--unit: string]: [string -> int, list<string> -> list<int>, record<a: string> -> record<a: int>]"#
+}
+
+impl StrSize {
+    fn on_string(&self, _: &MyPlugin, _: &EngineInterface, _: &EvaluatedCall, input: PipelineData, _: Option<String>)
+        -> Result<PipelineData, LabeledError> {
+        Ok(input)
+    }
+
+    fn on_list(&self, _: &MyPlugin, _: &EngineInterface, _: &EvaluatedCall, input: PipelineData, _: Option<String>)
+        -> Result<PipelineData, LabeledError> {
+        Ok(input)
+    }
+
+    fn on_record(&self, _: &MyPlugin, _: &EngineInterface, _: &EvaluatedCall, input: PipelineData, _: Option<String>)
+        -> Result<PipelineData, LabeledError> {
+        Ok(input)
+    }
+}
+
+fn main() {
+    assert_eq!(PluginCommand::signature(&StrSize).input_output_types.len(), 3);
+}
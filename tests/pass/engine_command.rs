@@ -0,0 +1,54 @@
+use nu_protocol::engine::{Call, Command, EngineState, Stack};
+use nu_protocol::{PipelineData, ShellError, Span, Value};
+
+#[nu_signature::engine_command(r#"
+    # Say hello
+    extern "str hello" [name: string, --loud]: nothing -> table<name: string, pattern: glob, patterns: list<glob>>
+"#)]
+#[command(output = Row)]
+#[derive(Clone)]
+pub struct StrHello;
+
+fn str_hello(_: &StrHello, _: &EngineState, _: &mut Stack, _: &Call, _: PipelineData) -> Result<Vec<Row>, ShellError> {
+    Ok(vec![Row {
+        name: String::from("nu"),
+        pattern: nu_protocol::NuGlob::DoNotExpand(String::from("*")),
+        patterns: vec![nu_protocol::NuGlob::Expand(String::from("*.rs"))],
+    }])
+}
+
+#[nu_signature::engine_command(r#"
+    # Echo the lists
+    extern "echo lists" []: [nothing -> list<string>, list<int> -> table<a: int>]
+"#)]
+#[command(dispatch, check_output)]
+#[derive(Clone)]
+pub struct EchoLists;
+
+impl EchoLists {
+    fn on_nothing(&self, _: &EngineState, _: &mut Stack, _: &Call, _: PipelineData) -> Result<PipelineData, ShellError> {
+        Ok(PipelineData::Value(Value::test_list(vec![]), None))
+    }
+
+    fn on_list(&self, _: &EngineState, _: &mut Stack, _: &Call, input: PipelineData) -> Result<PipelineData, ShellError> {
+        Ok(input)
+    }
+}
+
+fn main() {
+    let engine_state = EngineState::new();
+    let mut stack = Stack::new();
+    let call = nu_protocol::ast::Call::new(Span::test_data());
+    let call = Call::from(&call);
+
+    assert_eq!(StrHello.name(), "str hello");
+    assert_eq!(StrHello.signature().named.len(), 2);
+    let output = StrHello.run(&engine_state, &mut stack, &call, PipelineData::Empty).unwrap().into_value(Span::test_data()).unwrap();
+    assert_eq!(output.get_type().to_string(), "table<name: string, pattern: glob, patterns: list<glob>>");
+
+    assert!(EchoLists.run(&engine_state, &mut stack, &call, PipelineData::Empty).is_ok());
+    let empty = PipelineData::Value(Value::test_list(vec![]), None);
+    assert!(EchoLists.run(&engine_state, &mut stack, &call, empty).is_ok());
+    let strings = PipelineData::Value(Value::test_list(vec![Value::test_string("a")]), None);
+    assert!(EchoLists.run(&engine_state, &mut stack, &call, strings).is_err());
+}
@@ -0,0 +1,101 @@
+use nu_plugin::{EngineInterface, EvaluatedCall, Plugin, PluginCommand, SimplePluginCommand};
+use nu_protocol::{FromValue, LabeledError, PipelineData, Record, Value};
+
+pub struct MyPlugin;
+
+impl Plugin for MyPlugin {
+    fn version(&self) -> String {
+        String::from("0.1.0")
+    }
+
+    fn commands(&self) -> Vec<Box<dyn PluginCommand<Plugin = Self>>> {
+        vec![Box::new(Hello), Box::new(LsFiles)]
+    }
+}
+
+nu_signature::plugin_command! {
+    /// Says hello
+    pub struct Hello for MyPlugin;
+    r#"
+        # Say hello
+        @search-terms greet
+        @example "Say hello" { hello world } --result "hello world"
+        extern hello [
+            name: string                        # who to greet
+            times?: int = 1                     # how many times
+            ...others: string                   # more names
+            --kind(-k): string@[self other 2d]  # kind of greeting
+            --config: record<type: string, owner: record<id: int>, comment: any>
+            --rows: table<name: string, size: filesize>
+            --loud(-l)                          # shout
+        ]: nothing -> string
+    "#
+}
+
+#[allow(clippy::too_many_arguments)]
+fn hello(
+    _plugin: &MyPlugin,
+    _engine: &EngineInterface,
+    call: &EvaluatedCall,
+    _input: &Value,
+    name: String,
+    times: i64,
+    others: Vec<String>,
+    kind: Option<HelloKind>,
+    config: Option<HelloConfig>,
+    rows: Option<Vec<HelloRows>>,
+    loud: bool,
+) -> Result<Value, LabeledError> {
+    let kind = kind.map_or("", |kind| kind.as_str());
+    let owner = config.map(|config| (config.r#type, config.owner.id, config.comment));
+    let sizes = rows.unwrap_or_default().iter().map(|row| row.size).sum::<i64>();
+    let text = format!("{} {} {:?} {} {:?} {} {}", name, times, others, kind, owner, sizes, loud);
+    Ok(Value::string(text, call.head))
+}
+
+nu_signature::plugin_command! {
+    #[command(output = File)]
+    pub struct LsFiles for MyPlugin;
+    r#"extern "ls files" [pattern?: glob]: nothing -> table<name: string, size: filesize, pattern: glob, owner: record<id: int>>"#
+}
+
+fn ls_files(
+    _plugin: &MyPlugin,
+    _engine: &EngineInterface,
+    _call: &EvaluatedCall,
+    _input: PipelineData,
+    _pattern: Option<String>,
+) -> Result<Vec<File>, LabeledError> {
+    Ok(Vec::new())
+}
+
+fn main() {
+    let signature = SimplePluginCommand::signature(&Hello);
+    assert_eq!(signature.name, "hello");
+    assert_eq!(signature.required_positional[0].name, "name");
+    assert_eq!(SimplePluginCommand::examples(&Hello).len(), 1);
+    assert_eq!(PluginCommand::name(&LsFiles), "ls files");
+
+    assert_eq!(HelloKind::VALUES, ["self", "other", "2d"]);
+    assert_eq!(HelloKind::from_value(Value::test_string("self")).unwrap(), HelloKind::Self_);
+    assert_eq!(HelloKind::from_value(Value::test_string("2d")).unwrap().to_string(), "2d");
+    assert!(HelloKind::from_value(Value::test_string("none")).is_err());
+
+    let mut owner = Record::new();
+    owner.push("id", Value::test_int(1));
+    let mut config = Record::new();
+    config.push("type", Value::test_string("admin"));
+    config.push("owner", Value::test_record(owner));
+    let config = HelloConfig::from_value(Value::test_record(config)).unwrap();
+    assert_eq!((config.r#type.as_str(), config.owner.id), ("admin", 1));
+    assert!(config.comment.is_none());
+
+    let file = File {
+        name: String::from("a.txt"),
+        size: 10,
+        pattern: nu_protocol::NuGlob::Expand(String::from("*.txt")),
+        owner: FileOwner { id: 1 },
+    };
+    let value = nu_protocol::IntoValue::into_value(file, nu_protocol::Span::test_data());
+    assert_eq!(value.get_type().to_string(), "record<name: string, size: filesize, pattern: glob, owner: record<id: int>>");
+}
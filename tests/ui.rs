@@ -0,0 +1,25 @@
+//! Every error of the macros, with the expected diagnostics in `tests/ui/*.stderr`, and in
+//! `tests/ui/builtin_parser/*.stderr` for the errors located by the built-in parser. The code generated for the commands
+//! is compiled and run by `tests/pass/*.rs`.
+//! After an intended change of the diagnostics, the expected files are updated with `TRYBUILD=overwrite cargo test`.
+
+// the messages of the parse errors are the ones of nu-parser
+#[cfg(all(feature = "nu-parser", not(feature = "builtin-parser")))]
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
+
+#[cfg(any(feature = "builtin-parser", not(feature = "nu-parser")))]
+#[test]
+fn builtin_parser_ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/builtin_parser/*.rs");
+}
+
+#[test]
+fn generated_code() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/pass/*.rs");
+}
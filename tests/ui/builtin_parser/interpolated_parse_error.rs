@@ -0,0 +1,8 @@
+fn main() {
+    let _ = nu_signature::signature!(r#"
+        extern hello [
+            --port: int = {PORT}
+            name: foo
+        ]
+    "#, PORT = 8080);
+}
@@ -0,0 +1,6 @@
+nu_signature::plugin_command! {
+    pub struct Hello for MyPlugin;
+    "extern hello [--config: record<a-b: int, a_b: int>]"
+}
+
+fn main() {}
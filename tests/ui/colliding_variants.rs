@@ -0,0 +1,6 @@
+nu_signature::plugin_command! {
+    pub struct Convert for MyPlugin;
+    "extern convert [--kind: string@[a-b a_b]]"
+}
+
+fn main() {}
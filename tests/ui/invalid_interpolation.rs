@@ -0,0 +1,3 @@
+fn main() {
+    let _ = nu_signature::signature!("extern hello [--port: int = {PORT}]", 8080);
+}